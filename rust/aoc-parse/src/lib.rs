@@ -0,0 +1,367 @@
+//! Parsers of the shapes puzzle inputs come in, failing with the line and
+//! column of what could not be parsed instead of skipping it.
+use std::fmt;
+use std::str::FromStr;
+
+pub use regex::{Captures, Regex};
+
+/// What could not be parsed, at a one-based line and column
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// The lines of `input` that are not blank, with their one-based numbers
+fn lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+/// The one-based column of `token` in `line`, which it is a slice of
+fn column(line: &str, token: &str) -> usize {
+    let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+}
+
+/// Parses `token`, a slice of `line` at `line_number`
+fn token<T>(line_number: usize, line: &str, token: &str) -> ParseResult<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    token.parse().map_err(|e| ParseError {
+        line: line_number,
+        column: column(line, token),
+        message: format!("invalid value {:?}: {}", token, e),
+    })
+}
+
+/// A value on each line, such as the masses of day 1
+pub fn per_line<T>(input: &str) -> ParseResult<Vec<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    lines(input)
+        .map(|(n, line)| token(n, line, line.trim()))
+        .collect()
+}
+
+/// Values separated by commas on each line, such as the paths of day 3
+pub fn comma_separated_lines<T>(input: &str) -> ParseResult<Vec<Vec<T>>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    lines(input)
+        .map(|(n, line)| {
+            line.split(',')
+                .map(|value| token(n, line, value.trim()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Values separated by commas, over any number of lines, such as the
+/// programs of day 2
+pub fn comma_separated<T>(input: &str) -> ParseResult<Vec<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    Ok(comma_separated_lines(input)?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// A rectangle of characters, row by row from the top left
+#[derive(Debug, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<char>,
+}
+
+impl Grid {
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[char]> {
+        self.cells.chunks(self.width.max(1))
+    }
+}
+
+/// The characters of the lines of `input`, which must all be as long
+pub fn grid(input: &str) -> ParseResult<Grid> {
+    let mut grid = Grid {
+        width: 0,
+        height: 0,
+        cells: vec![],
+    };
+
+    for (n, line) in lines(input) {
+        let width = line.chars().count();
+        if grid.height == 0 {
+            grid.width = width;
+        } else if width != grid.width {
+            return Err(ParseError {
+                line: n,
+                column: width.min(grid.width) + 1,
+                message: format!("row of {} characters, expected {}", width, grid.width),
+            });
+        }
+
+        grid.cells.extend(line.chars());
+        grid.height += 1;
+    }
+    Ok(grid)
+}
+
+/// Lines that were not separated by a blank line
+#[derive(Debug, PartialEq)]
+pub struct Group<'a> {
+    /// The one-based number of the first line
+    pub line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+impl<'a> Group<'a> {
+    /// Parses each line of the group with `f`, failing at the line it fails
+    /// on
+    pub fn parse<T, E, F>(&self, f: F) -> ParseResult<Vec<T>>
+    where
+        E: fmt::Display,
+        F: Fn(&str) -> Result<T, E>,
+    {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                f(line).map_err(|e| ParseError {
+                    line: self.line + i,
+                    column: 1,
+                    message: e.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The groups of lines of `input` separated by blank lines
+pub fn groups(input: &str) -> Vec<Group<'_>> {
+    let mut groups: Vec<Group> = vec![];
+    let mut previous = 0;
+
+    for (n, line) in lines(input) {
+        match groups.last_mut() {
+            Some(group) if n == previous + 1 => group.lines.push(line),
+            _ => groups.push(Group {
+                line: n,
+                lines: vec![line],
+            }),
+        }
+        previous = n;
+    }
+    groups
+}
+
+/// A line matched by the pattern of `records`
+pub struct Record<'a> {
+    pub line: usize,
+    text: &'a str,
+    captures: Captures<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Parses the group `name` of the pattern
+    pub fn get<T>(&self, name: &str) -> ParseResult<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.captures.name(name).ok_or_else(|| ParseError {
+            line: self.line,
+            column: 1,
+            message: format!("no group {} in the pattern", name),
+        })?;
+
+        token(self.line, self.text, value.as_str())
+    }
+}
+
+/// A value on each line, read by `f` from the groups of `pattern`, which
+/// must match the whole line
+pub fn records<T, F>(input: &str, pattern: &Regex, f: F) -> ParseResult<Vec<T>>
+where
+    F: Fn(&Record) -> ParseResult<T>,
+{
+    // Anchored, as a leftmost match may stop short of the end of the line
+    // where a longer alternative would match all of it
+    let anchored = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| ParseError {
+        line: 1,
+        column: 1,
+        message: format!("invalid pattern {}: {}", pattern, e),
+    })?;
+
+    lines(input)
+        .map(|(n, line)| {
+            let captures = anchored.captures(line).ok_or_else(|| ParseError {
+                line: n,
+                column: 1,
+                message: format!("{:?} does not match {}", line, pattern),
+            })?;
+
+            f(&Record {
+                line: n,
+                text: line,
+                captures,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_per_line() {
+        assert_eq!(Ok(vec![12, 14]), per_line::<u32>("12\n\n 14 \n"));
+        assert_eq!(
+            Err(ParseError {
+                line: 3,
+                column: 2,
+                message: "invalid value \"1x\": invalid digit found in string".to_string()
+            }),
+            per_line::<u32>("12\n\n 1x\n")
+        );
+    }
+
+    #[test]
+    fn test_comma_separated() {
+        assert_eq!(
+            Ok(vec![1, -2, 3, 4]),
+            comma_separated::<i64>("1,-2\n3, 4\n")
+        );
+        assert_eq!(
+            Some((2, 4)),
+            comma_separated::<i64>("1,2\n3, x")
+                .err()
+                .map(|e| (e.line, e.column))
+        );
+        assert_eq!(
+            Ok(vec![
+                vec!["R8".to_string(), "U5".to_string()],
+                vec!["L2".to_string()]
+            ]),
+            comma_separated_lines::<String>("R8,U5\nL2\n")
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = grid("#.\n.#\n").unwrap();
+
+        assert_eq!((2, 2), (grid.width, grid.height));
+        assert_eq!(Some('#'), grid.get(1, 1));
+        assert_eq!(None, grid.get(2, 0));
+        assert_eq!(
+            vec![&['#', '.'][..], &['.', '#'][..]],
+            grid.rows().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Err(ParseError {
+                line: 2,
+                column: 3,
+                message: "row of 3 characters, expected 2".to_string()
+            }),
+            super::grid("#.\n.##\n")
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        let groups = groups("1\nb\n\n\nc\n");
+
+        assert_eq!(
+            vec![
+                Group {
+                    line: 1,
+                    lines: vec!["1", "b"]
+                },
+                Group {
+                    line: 5,
+                    lines: vec!["c"]
+                }
+            ],
+            groups
+        );
+        assert_eq!(
+            Some(2),
+            groups[0]
+                .parse(|line| line.parse::<u32>().map_err(|_| "not a number"))
+                .err()
+                .map(|e| e.line)
+        );
+    }
+
+    #[test]
+    fn test_records() {
+        let pattern = Regex::new(r"(?P<from>\d+)-(?P<to>\d+)").unwrap();
+        let parse = |input| {
+            records(input, &pattern, |r| {
+                Ok((r.get::<u8>("from")?, r.get::<u8>("to")?))
+            })
+        };
+
+        assert_eq!(Ok(vec![(1, 3), (10, 20)]), parse("1-3\n10-20\n"));
+        assert_eq!(
+            Some((2, 4)),
+            parse("1-3\n10-999\n").err().map(|e| (e.line, e.column))
+        );
+        assert_eq!(
+            Err(ParseError {
+                line: 1,
+                column: 1,
+                message: "\"1-3x\" does not match (?P<from>\\d+)-(?P<to>\\d+)".to_string()
+            }),
+            parse("1-3x")
+        );
+    }
+
+    #[test]
+    fn test_records_match_whole_lines() {
+        let pattern = Regex::new(r"(?P<from>\d+)|(?P<range>\d+-\d+)").unwrap();
+
+        assert_eq!(
+            Ok(vec!["1-3".to_string()]),
+            records("1-3\n", &pattern, |r| r.get("range"))
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Benchmarks of parsing the input and solving each part of every day, for
+//! `aoc bench` to compare with the previous run.
+use aoc::Part;
+use aoc_runner::inputs::Inputs;
+use aoc_runner::{default_cache, Day, DAYS};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+fn bench_day(c: &mut Criterion, inputs: &Inputs, day: &Day) {
+    let skip = |e: &dyn std::fmt::Display| {
+        eprintln!("Skipping {} day {}: {}", day.year, day.day, e);
+    };
+    let input = match day.input(inputs) {
+        Ok(input) => input,
+        Err(e) => return skip(&e),
+    };
+    let parsed = match day.solution.parse_input(&input) {
+        Ok(parsed) => parsed,
+        Err(e) => return skip(&e),
+    };
+
+    let mut group = c.benchmark_group(format!("{}/day{:02}", day.year, day.day));
+    group.bench_function("parse", |b| {
+        b.iter(|| day.solution.parse_input(black_box(&input)))
+    });
+    group.bench_function("part1", |b| b.iter(|| parsed.solve(black_box(Part::One))));
+    group.bench_function("part2", |b| b.iter(|| parsed.solve(black_box(Part::Two))));
+    group.finish();
+}
+
+fn days(c: &mut Criterion) {
+    let inputs = Inputs::from_env(&default_cache());
+
+    for day in DAYS {
+        bench_day(c, &inputs, day);
+    }
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
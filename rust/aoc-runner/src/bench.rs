@@ -0,0 +1,249 @@
+//! Keeps the results of the benchmarks of the days, to compare each run with
+//! the one before it.
+//!
+//! The results are read from where criterion leaves its estimates, and every
+//! run is appended as a line of JSON to a history file.
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The mean time in nanoseconds of each benchmark, such as `2019/day03/part1`
+pub type Results = BTreeMap<String, f64>;
+
+/// The relative slowdown above which a benchmark is flagged as a regression
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Reads the latest estimates criterion left in `dir`
+pub fn read_estimates(dir: &Path) -> io::Result<Results> {
+    let mut results = Results::new();
+    collect_estimates(dir, dir, &mut results)?;
+    Ok(results)
+}
+
+fn collect_estimates(root: &Path, dir: &Path, results: &mut Results) -> io::Result<()> {
+    let estimates = dir.join("new").join("estimates.json");
+    if estimates.is_file() {
+        let value: Value = serde_json::from_str(&fs::read_to_string(&estimates)?)?;
+        let mean = value["mean"]["point_estimate"].as_f64().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("No mean in {}", estimates.display()),
+            )
+        })?;
+
+        // The id as it was given, since the directory names are sanitized
+        let id = fs::read_to_string(dir.join("new").join("benchmark.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .and_then(|value| value["full_id"].as_str().map(String::from))
+            .unwrap_or_else(|| {
+                let path = dir.strip_prefix(root).unwrap().to_string_lossy();
+                path.replace('\\', "/")
+            });
+        results.insert(id, mean);
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && !path.ends_with("report") {
+            collect_estimates(root, &path, results)?;
+        }
+    }
+    Ok(())
+}
+
+/// The results of the last run in the history at `path`, if there is one
+pub fn last_run(path: &Path) -> io::Result<Option<Results>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut last = None;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let run: Value = serde_json::from_str(&line)?;
+        last = Some(parse_results(&run["results"]));
+    }
+    Ok(last)
+}
+
+fn parse_results(value: &Value) -> Results {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, mean)| Some((id.clone(), mean.as_f64()?)))
+        .collect()
+}
+
+/// Appends `results` to the history at `path`, as a new run
+pub fn append_run(path: &Path, results: &Results) -> io::Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let results = results
+        .iter()
+        .map(|(id, &mean)| (id.clone(), json!(mean)))
+        .collect::<Map<_, _>>();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", json!({ "time": time, "results": results }))
+}
+
+/// A benchmark compared with the previous run
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    pub id: String,
+    pub previous: Option<f64>,
+    pub current: f64,
+    pub regression: bool,
+}
+
+impl Comparison {
+    /// The relative change from the previous run, positive when slower
+    pub fn change(&self) -> Option<f64> {
+        self.previous
+            .map(|previous| (self.current - previous) / previous)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<24} {:>12}", self.id, format_time(self.current))?;
+        match (self.previous, self.change()) {
+            (Some(previous), Some(change)) => write!(
+                f,
+                " {:>12} {:>+8.1}%{}",
+                format_time(previous),
+                change * 100.0,
+                if self.regression { "  REGRESSION" } else { "" }
+            ),
+            _ => write!(f, " {:>12}", "new"),
+        }
+    }
+}
+
+fn format_time(ns: f64) -> String {
+    match ns {
+        ns if ns < 1e3 => format!("{:.1} ns", ns),
+        ns if ns < 1e6 => format!("{:.1} µs", ns / 1e3),
+        ns if ns < 1e9 => format!("{:.1} ms", ns / 1e6),
+        ns => format!("{:.2} s", ns / 1e9),
+    }
+}
+
+/// Compares `current` with `previous`, flagging the benchmarks that got
+/// slower by more than `threshold`
+pub fn compare(previous: &Results, current: &Results, threshold: f64) -> Vec<Comparison> {
+    current
+        .iter()
+        .map(|(id, &current)| {
+            let previous = previous.get(id).copied();
+            Comparison {
+                id: id.clone(),
+                previous,
+                current,
+                regression: previous.is_some_and(|previous| current > previous * (1.0 + threshold)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn results(entries: &[(&str, f64)]) -> Results {
+        entries
+            .iter()
+            .map(|&(id, mean)| (id.to_string(), mean))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_estimates() {
+        let dir = tempfile::tempdir().unwrap();
+        let estimates = r#"{"mean":{"point_estimate":1500.5,"standard_error":2.0}}"#;
+        for bench in &["2019_day03/part1", "2019_day03/part2"] {
+            let new = dir.path().join(bench).join("new");
+            fs::create_dir_all(&new).unwrap();
+            fs::write(new.join("estimates.json"), estimates).unwrap();
+        }
+        fs::write(
+            dir.path().join("2019_day03/part2/new/benchmark.json"),
+            r#"{"full_id":"2019/day03/part2"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("report")).unwrap();
+
+        assert_eq!(
+            results(&[("2019/day03/part2", 1500.5), ("2019_day03/part1", 1500.5)]),
+            read_estimates(dir.path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+
+        assert_eq!(None, last_run(&path).unwrap());
+        append_run(&path, &results(&[("a", 1.0)])).unwrap();
+        append_run(&path, &results(&[("a", 2.0), ("b", 3.0)])).unwrap();
+        assert_eq!(
+            Some(results(&[("a", 2.0), ("b", 3.0)])),
+            last_run(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let previous = results(&[("faster", 100.0), ("noise", 100.0), ("slower", 100.0)]);
+        let current = results(&[
+            ("faster", 50.0),
+            ("new", 10.0),
+            ("noise", 105.0),
+            ("slower", 150.0),
+        ]);
+
+        let comparisons = compare(&previous, &current, DEFAULT_THRESHOLD);
+
+        let regressions = comparisons
+            .iter()
+            .filter(|c| c.regression)
+            .map(|c| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["slower"], regressions);
+        assert_eq!(Some(-0.5), comparisons[0].change());
+        assert_eq!(None, comparisons[1].change());
+    }
+
+    #[test]
+    fn test_display_comparison() {
+        let comparison = Comparison {
+            id: "2019/day02/part2".to_string(),
+            previous: Some(10e6),
+            current: 15e6,
+            regression: true,
+        };
+
+        assert_eq!(
+            "2019/day02/part2              15.0 ms      10.0 ms    +50.0%  REGRESSION",
+            comparison.to_string()
+        );
+    }
+}
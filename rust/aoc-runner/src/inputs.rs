@@ -0,0 +1,264 @@
+//! The puzzle inputs, cached in a directory by year and day and fetched from
+//! the puzzle server the first time they are needed.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq)]
+pub enum InputError {
+    Io(io::ErrorKind),
+    /// The input is not cached, and there is no session to fetch it with
+    NotCached {
+        year: u32,
+        day: u32,
+    },
+    /// The request for the input failed, such as when there is no server
+    Http(String),
+    /// The server answered with a status other than 200
+    Status(u16),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io(kind) => write!(f, "{:?}", kind),
+            InputError::NotCached { year, day } => write!(
+                f,
+                "The input of {} day {} is not cached, set AOC_SESSION to fetch it",
+                year, day
+            ),
+            InputError::Http(message) => write!(f, "Could not fetch input: {}", message),
+            InputError::Status(status) => write!(f, "Could not fetch input: status {}", status),
+        }
+    }
+}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        InputError::Io(e.kind())
+    }
+}
+
+impl From<InputError> for aoc::RunError {
+    fn from(e: InputError) -> Self {
+        aoc::RunError::Input(e.to_string())
+    }
+}
+
+pub type InputResult<T> = Result<T, InputError>;
+
+pub const DEFAULT_ENDPOINT: &str = "https://adventofcode.com";
+
+pub struct Inputs {
+    cache: PathBuf,
+    endpoint: String,
+    session: Option<String>,
+}
+
+impl Inputs {
+    /// Inputs cached in `cache`, that can't be fetched until there is a
+    /// session
+    pub fn new<P: Into<PathBuf>>(cache: P) -> Self {
+        Inputs {
+            cache: cache.into(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            session: None,
+        }
+    }
+
+    /// Inputs configured by the environment: `AOC_CACHE` for the cache
+    /// directory, `AOC_ENDPOINT` for the server and `AOC_SESSION` for the
+    /// session token
+    pub fn from_env(default_cache: &Path) -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let mut inputs =
+            Inputs::new(var("AOC_CACHE").map_or_else(|| default_cache.into(), PathBuf::from));
+        if let Some(endpoint) = var("AOC_ENDPOINT") {
+            inputs = inputs.endpoint(&endpoint);
+        }
+        if let Some(session) = var("AOC_SESSION") {
+            inputs = inputs.session(&session);
+        }
+        inputs
+    }
+
+    /// Fetches from `endpoint` instead of the puzzle server
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetches with the token of the session cookie of a logged in user
+    pub fn session(mut self, session: &str) -> Self {
+        self.session = Some(session.trim().to_string());
+        self
+    }
+
+    /// Where the input of `day` of `year` is cached
+    pub fn path(&self, year: u32, day: u32) -> PathBuf {
+        self.cache
+            .join(year.to_string())
+            .join(format!("day{:02}.txt", day))
+    }
+
+    /// The input of `day` of `year`, fetched only if it is not cached yet
+    pub fn get(&self, year: u32, day: u32) -> InputResult<String> {
+        let path = self.path(year, day);
+        match fs::read_to_string(&path) {
+            Ok(input) => return Ok(input),
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            Err(_) => {}
+        }
+
+        let input = self.fetch(year, day)?;
+
+        // Written next to it first, so that an interrupted write is never
+        // taken for the input
+        fs::create_dir_all(path.parent().unwrap())?;
+        let partial = path.with_extension("part");
+        fs::write(&partial, &input)?;
+        fs::rename(&partial, &path)?;
+
+        Ok(input)
+    }
+
+    fn fetch(&self, year: u32, day: u32) -> InputResult<String> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or(InputError::NotCached { year, day })?;
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let mut response = agent
+            .get(&format!("{}/{}/day/{}/input", self.endpoint, year, day))
+            .header("Cookie", &format!("session={}", session))
+            .header("User-Agent", "github.com/arthurc/adventofcode")
+            .call()
+            .map_err(|e| InputError::Http(e.to_string()))?;
+
+        match response.status().as_u16() {
+            200 => response
+                .body_mut()
+                .read_to_string()
+                .map_err(|e| InputError::Http(e.to_string())),
+            status => Err(InputError::Status(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use tiny_http::{Response, Server};
+
+    /// A stand-in for the puzzle server, answering with the input of any day
+    /// to the session `secret`
+    struct MockServer {
+        server: Arc<Server>,
+        requests: Arc<Mutex<Vec<String>>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl MockServer {
+        fn start() -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+            let requests = Arc::new(Mutex::new(vec![]));
+
+            let thread = {
+                let (server, requests) = (server.clone(), requests.clone());
+                thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        let authorized = request.headers().iter().any(|header| {
+                            header.field.equiv("Cookie") && header.value == "session=secret"
+                        });
+                        let url = request.url().to_string();
+                        requests.lock().unwrap().push(url.clone());
+
+                        let response = if authorized {
+                            Response::from_string(format!("input of {}\n", url))
+                        } else {
+                            Response::from_string("Puzzle inputs differ by user.")
+                                .with_status_code(400)
+                        };
+                        request.respond(response).unwrap();
+                    }
+                })
+            };
+
+            MockServer {
+                server,
+                requests,
+                thread: Some(thread),
+            }
+        }
+
+        fn endpoint(&self) -> String {
+            format!("http://{}/", self.server.server_addr())
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            self.thread.take().unwrap().join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_fetches_once() {
+        let cache = tempfile::tempdir().unwrap();
+        let server = MockServer::start();
+        let inputs = Inputs::new(cache.path())
+            .endpoint(&server.endpoint())
+            .session("secret\n");
+
+        let expected = "input of /2019/day/4/input\n".to_string();
+        assert_eq!(Ok(expected.clone()), inputs.get(2019, 4));
+        assert_eq!(Ok(expected.clone()), inputs.get(2019, 4));
+        assert_eq!(vec!["/2019/day/4/input"], server.requests());
+
+        let cached = cache.path().join("2019").join("day04.txt");
+        assert_eq!(cached, inputs.path(2019, 4));
+        assert_eq!(expected, fs::read_to_string(cached).unwrap());
+    }
+
+    #[test]
+    fn test_cached_input_needs_no_session() {
+        let cache = tempfile::tempdir().unwrap();
+        fs::create_dir(cache.path().join("2019")).unwrap();
+        fs::write(cache.path().join("2019").join("day01.txt"), "12\n").unwrap();
+        let inputs = Inputs::new(cache.path());
+
+        assert_eq!(Ok("12\n".to_string()), inputs.get(2019, 1));
+        assert_eq!(
+            Err(InputError::NotCached { year: 2019, day: 2 }),
+            inputs.get(2019, 2)
+        );
+    }
+
+    #[test]
+    fn test_failed_fetch_is_not_cached() {
+        let cache = tempfile::tempdir().unwrap();
+        let server = MockServer::start();
+        let inputs = Inputs::new(cache.path())
+            .endpoint(&server.endpoint())
+            .session("guess");
+
+        assert_eq!(Err(InputError::Status(400)), inputs.get(2019, 4));
+        assert_eq!(Err(InputError::Status(400)), inputs.get(2019, 4));
+        assert_eq!(2, server.requests().len());
+        assert!(!inputs.path(2019, 4).exists());
+    }
+}
@@ -0,0 +1,352 @@
+//! Finds the solution of any day, and reads the command line telling which
+//! one to run.
+use aoc::{Part, Solver};
+use inputs::{InputResult, Inputs};
+use report::Format;
+use std::path::{Path, PathBuf};
+
+pub mod bench;
+pub mod inputs;
+pub mod report;
+pub mod scaffold;
+pub mod verify;
+
+/// A day of a year of puzzles, with its solution
+pub struct Day {
+    pub year: u32,
+    pub day: u32,
+    pub solution: &'static dyn Solver,
+}
+
+impl Day {
+    /// The input of the day, as it is checked in next to its crate
+    pub fn input_path(&self) -> PathBuf {
+        self.dir().join("input.txt")
+    }
+
+    /// The input of the day, checked in or from `inputs`
+    pub fn input(&self, inputs: &Inputs) -> InputResult<String> {
+        match checked_in(&self.input_path()) {
+            Some(input) => Ok(input),
+            None => inputs.get(self.year, self.day),
+        }
+    }
+
+    /// The expected answers of the day, next to its input
+    pub fn answers_path(&self) -> PathBuf {
+        self.dir().join("answers.toml")
+    }
+
+    fn dir(&self) -> PathBuf {
+        workspace().join(format!("day{:02}", self.day))
+    }
+}
+
+/// The input at `path`, unless it is missing or still the empty placeholder
+/// written by `aoc new`
+fn checked_in(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .filter(|input| !input.trim().is_empty())
+}
+
+/// The directory of the workspace of the days
+pub fn workspace() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// Where inputs are cached unless `AOC_CACHE` is set, next to the days
+pub fn default_cache() -> PathBuf {
+    workspace().join(".inputs")
+}
+
+pub const DAYS: &[Day] = &[
+    Day {
+        year: 2019,
+        day: 1,
+        solution: &day01::Day01,
+    },
+    Day {
+        year: 2019,
+        day: 2,
+        solution: &day02::Day02,
+    },
+    Day {
+        year: 2019,
+        day: 3,
+        solution: &day03::Day03,
+    },
+    Day {
+        year: 2019,
+        day: 4,
+        solution: &day04::Day04,
+    },
+    Day {
+        year: 2019,
+        day: 5,
+        solution: &day05::solution::Day05,
+    },
+];
+
+pub fn find(year: u32, day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.year == year && d.day == day)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Solves `part` of a day, or both parts if there is none, printing the
+    /// answers in `format`
+    Run {
+        year: u32,
+        day: u32,
+        part: Option<Part>,
+        input: Option<PathBuf>,
+        format: Format,
+    },
+    /// Solves both parts of every day in parallel, printing the answers in
+    /// `format` and as a table of timings if it is text
+    RunAll { format: Format },
+    /// Checks the answers of every day against the stored ones
+    Verify,
+    /// Creates the crate of a new day
+    New { year: u32, day: u32 },
+    /// Benchmarks every day, flagging what got slower than the last time by
+    /// more than `threshold`
+    Bench { threshold: f64 },
+}
+
+pub const USAGE: &str =
+    "Usage: aoc run <year> <day> [--part 1|2] [--input <path>] [--format text|json]
+       aoc run --all [--format text|json]
+       aoc verify
+       aoc new <year> <day>
+       aoc bench [--threshold <percent>]";
+
+/// Parses the arguments following the name of the program
+pub fn parse_args<S: AsRef<str>>(args: &[S]) -> Result<Command, String> {
+    let mut args = args.iter().map(AsRef::as_ref);
+
+    match args.next() {
+        Some("run") => {}
+        Some("bench") => return parse_bench(args),
+        Some("new") => {
+            let year = number(args.next(), "year")?;
+            let day = number(args.next(), "day")?;
+            return match args.next() {
+                Some(arg) => Err(format!("Unexpected argument {}", arg)),
+                None => Ok(Command::New { year, day }),
+            };
+        }
+        Some("verify") => {
+            return match args.next() {
+                Some(arg) => Err(format!("Unexpected argument {}", arg)),
+                None => Ok(Command::Verify),
+            }
+        }
+        Some(command) => return Err(format!("Unknown command {}", command)),
+        None => return Err(USAGE.to_string()),
+    }
+
+    let mut args = args.peekable();
+    if args.peek() == Some(&"--all") {
+        args.next();
+        return parse_run_all(args);
+    }
+
+    let year = number(args.next(), "year")?;
+    let day = number(args.next(), "day")?;
+    let mut part = None;
+    let mut input = None;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of {}", arg))?;
+        match arg {
+            "--part" => part = Some(value.parse()?),
+            "--input" => input = Some(PathBuf::from(value)),
+            "--format" => format = value.parse()?,
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    Ok(Command::Run {
+        year,
+        day,
+        part,
+        input,
+        format,
+    })
+}
+
+fn parse_run_all<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command, String> {
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of {}", arg))?;
+        match arg {
+            "--format" => format = value.parse()?,
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    Ok(Command::RunAll { format })
+}
+
+fn parse_bench<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command, String> {
+    let mut threshold = bench::DEFAULT_THRESHOLD;
+
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of {}", arg))?;
+        match arg {
+            "--threshold" => {
+                threshold = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|percent| *percent >= 0.0)
+                    .ok_or_else(|| format!("Invalid threshold {}, expected a percentage", value))?
+                    / 100.0
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    Ok(Command::Bench { threshold })
+}
+
+fn number(arg: Option<&str>, name: &str) -> Result<u32, String> {
+    let arg = arg.ok_or_else(|| format!("Missing {}\n{}", name, USAGE))?;
+    arg.parse()
+        .map_err(|_| format!("Invalid {} {}, expected a number", name, arg))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        assert_eq!(5, find(2019, 5).unwrap().day);
+        assert!(find(2019, 25).is_none());
+        assert!(find(2018, 1).is_none());
+    }
+
+    #[test]
+    fn test_days_with_answers_have_inputs() {
+        for day in DAYS.iter().filter(|d| d.answers_path().exists()) {
+            assert!(checked_in(&day.input_path()).is_some(), "day {}", day.day);
+        }
+    }
+
+    #[test]
+    fn test_checked_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.txt");
+
+        assert_eq!(None, checked_in(&path));
+        std::fs::write(&path, "\n").unwrap();
+        assert_eq!(None, checked_in(&path));
+        std::fs::write(&path, "12\n14\n").unwrap();
+        assert_eq!(Some("12\n14\n".to_string()), checked_in(&path));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            Ok(Command::Run {
+                year: 2019,
+                day: 3,
+                part: None,
+                input: None,
+                format: Format::Text
+            }),
+            parse_args(&["run", "2019", "3"])
+        );
+        assert_eq!(
+            Ok(Command::Run {
+                year: 2019,
+                day: 3,
+                part: Some(Part::Two),
+                input: Some(PathBuf::from("in.txt")),
+                format: Format::Json
+            }),
+            parse_args(&[
+                "run", "2019", "3", "--input", "in.txt", "--part", "2", "--format", "json"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_run_all() {
+        assert_eq!(
+            Ok(Command::RunAll {
+                format: Format::Text
+            }),
+            parse_args(&["run", "--all"])
+        );
+        assert_eq!(
+            Ok(Command::RunAll {
+                format: Format::Json
+            }),
+            parse_args(&["run", "--all", "--format", "json"])
+        );
+        assert_eq!(
+            Err("Unknown option --part".to_string()),
+            parse_args(&["run", "--all", "--part", "1"])
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(Ok(Command::Verify), parse_args(&["verify"]));
+        assert_eq!(
+            Err("Unexpected argument 2019".to_string()),
+            parse_args(&["verify", "2019"])
+        );
+        assert_eq!(
+            Ok(Command::New { year: 2019, day: 6 }),
+            parse_args(&["new", "2019", "6"])
+        );
+        assert_eq!(
+            Ok(Command::Bench { threshold: 0.1 }),
+            parse_args(&["bench"])
+        );
+        assert_eq!(
+            Ok(Command::Bench { threshold: 0.25 }),
+            parse_args(&["bench", "--threshold", "25"])
+        );
+        assert_eq!(
+            Err("Invalid threshold -5, expected a percentage".to_string()),
+            parse_args(&["bench", "--threshold", "-5"])
+        );
+        assert_eq!(
+            Err("Unknown command walk".to_string()),
+            parse_args(&["walk"])
+        );
+        assert_eq!(
+            Err("Invalid day x, expected a number".to_string()),
+            parse_args(&["run", "2019", "x"])
+        );
+        assert_eq!(
+            Err("Missing value of --part".to_string()),
+            parse_args(&["run", "2019", "1", "--part"])
+        );
+        assert_eq!(
+            Err("Unknown part 3, expected 1 or 2".to_string()),
+            parse_args(&["run", "2019", "1", "--part", "3"])
+        );
+        assert_eq!(
+            Err("Unknown option --fast".to_string()),
+            parse_args(&["run", "2019", "1", "--fast", "yes"])
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use aoc::{exit_on_error, Part, RunError, RunResult};
+use aoc_runner::bench::{self, compare};
+use aoc_runner::inputs::Inputs;
+use aoc_runner::report::{table, Format, Record};
+use aoc_runner::scaffold::scaffold;
+use aoc_runner::verify::{verify, Status};
+use aoc_runner::{default_cache, find, parse_args, workspace, Command, Day, DAYS};
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::process;
+
+/// Solves the puzzle of a day, or verifies them all, see `USAGE`
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let inputs = Inputs::from_env(&default_cache());
+
+    let result = parse_args(&args)
+        .map_err(RunError::Usage)
+        .and_then(|command| match command {
+            Command::Run {
+                year,
+                day,
+                part,
+                input,
+                format,
+            } => run(&inputs, year, day, part, input, format),
+            Command::RunAll { format } => run_all(&inputs, format),
+            Command::Verify => verify_all(&inputs),
+            Command::New { year, day } => {
+                for path in scaffold(&workspace(), year, day)? {
+                    println!("Wrote {}", path.display());
+                }
+                Ok(())
+            }
+            Command::Bench { threshold } => bench_all(threshold),
+        });
+
+    exit_on_error(result);
+}
+
+fn run(
+    inputs: &Inputs,
+    year: u32,
+    day: u32,
+    part: Option<Part>,
+    input: Option<PathBuf>,
+    format: Format,
+) -> RunResult<()> {
+    let found = find(year, day)
+        .ok_or_else(|| RunError::Usage(format!("No solution for {} day {}", year, day)))?;
+    let parts = part.map_or_else(|| Part::ALL.to_vec(), |part| vec![part]);
+
+    let input = match input {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| RunError::Input(format!("Could not read {}: {}", path.display(), e)))?,
+        None => found.input(inputs)?,
+    };
+
+    for part in parts {
+        println!("{}", solve(found, part, &input)?.format(format));
+    }
+    Ok(())
+}
+
+/// Solves every part of every day that has an input on a thread pool, then
+/// prints them in order, failing if any part could not be solved
+fn run_all(inputs: &Inputs, format: Format) -> RunResult<()> {
+    let days = DAYS
+        .par_iter()
+        .map(|day| (day, day.input(inputs)))
+        .collect::<Vec<_>>();
+
+    let mut parts = vec![];
+    for (day, input) in &days {
+        match input {
+            Ok(input) => parts.extend(Part::ALL.iter().map(|&part| (*day, part, input))),
+            Err(e) => eprintln!("Skipped {} day {}: {}", day.year, day.day, e),
+        }
+    }
+
+    let results = parts
+        .into_par_iter()
+        .map(|(day, part, input)| solve(day, part, input))
+        .collect::<Vec<_>>();
+
+    let (mut records, mut errors) = (vec![], vec![]);
+    for result in results {
+        match result {
+            Ok(record) => records.push(record),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    match format {
+        Format::Text => print!("{}", table(&records)),
+        Format::Json => {
+            for record in &records {
+                println!("{}", record.format(format));
+            }
+        }
+    }
+
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if !errors.is_empty() {
+        return Err(RunError::Solver(format!(
+            "{} parts could not be solved",
+            errors.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Solves `part` of `day`, with the day and part in the message of any error
+fn solve(day: &Day, part: Part, input: &str) -> RunResult<Record> {
+    let timed = day.solution.solve_timed(part, input).map_err(|e| {
+        let message = format!("{} day {} part {}: {}", day.year, day.day, part, e);
+        match RunError::from(e) {
+            RunError::Input(_) => RunError::Input(message),
+            _ => RunError::Solver(message),
+        }
+    })?;
+
+    Ok(Record {
+        year: day.year,
+        day: day.day,
+        part,
+        timed,
+    })
+}
+
+/// Prints the check of every part, failing if any answer is wrong
+fn verify_all(inputs: &Inputs) -> RunResult<()> {
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+
+    for check in DAYS.iter().flat_map(|day| verify(day, inputs)) {
+        println!("{}", check);
+        match check.status {
+            Status::Pass => passed += 1,
+            Status::Missing(_) => missing += 1,
+            _ => failed += 1,
+        }
+    }
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    if failed > 0 {
+        return Err(RunError::Solver(format!("{} answers are wrong", failed)));
+    }
+    Ok(())
+}
+
+/// Runs the benchmarks with cargo, then compares their results with the
+/// previous run and adds them to the history
+fn bench_all(threshold: f64) -> RunResult<()> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = process::Command::new(cargo)
+        .args(["bench", "-p", "aoc-runner", "--bench", "days"])
+        .current_dir(workspace())
+        .status()
+        .map_err(|e| RunError::Solver(format!("Could not run cargo bench: {}", e)))?;
+    if !status.success() {
+        return Err(RunError::Solver(format!(
+            "cargo bench failed with {}",
+            status
+        )));
+    }
+
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| workspace().join("target"), PathBuf::from);
+    let history = workspace().join(".bench").join("history.jsonl");
+
+    let current = bench::read_estimates(&target.join("criterion"))
+        .map_err(|e| RunError::Input(format!("Could not read benchmark results: {}", e)))?;
+    let previous = bench::last_run(&history)
+        .map_err(|e| RunError::Input(format!("Could not read {}: {}", history.display(), e)))?
+        .unwrap_or_default();
+
+    let comparisons = compare(&previous, &current, threshold);
+    println!(
+        "{:<24} {:>12} {:>12} {:>9}",
+        "benchmark", "mean", "previous", "change"
+    );
+    for comparison in &comparisons {
+        println!("{}", comparison);
+    }
+
+    bench::append_run(&history, &current)
+        .map_err(|e| RunError::Input(format!("Could not write {}: {}", history.display(), e)))?;
+
+    let regressions = comparisons.iter().filter(|c| c.regression).count();
+    if regressions > 0 {
+        println!(
+            "{} benchmarks got slower by more than {}%",
+            regressions,
+            threshold * 100.0
+        );
+    }
+    Ok(())
+}
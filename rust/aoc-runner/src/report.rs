@@ -0,0 +1,173 @@
+//! How the answers of `aoc run` are printed, as text for people or as lines
+//! of JSON for scripts, and as a table of timings when every day is run.
+use aoc::{Answer, Part, Timed};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Text,
+    /// A JSON object on each line, see `Record::to_json`
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format {}, expected text or json", s)),
+        }
+    }
+}
+
+/// The answer to a part of a day, and how long it took to solve it
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub year: u32,
+    pub day: u32,
+    pub part: Part,
+    pub timed: Timed,
+}
+
+impl Record {
+    /// `{"year", "day", "part", "answer", "elapsed_ms", "parse_ms",
+    /// "solve_ms"}`, where the answer is a number unless the puzzle asks for
+    /// text
+    pub fn to_json(&self) -> Value {
+        let answer = match &self.timed.answer {
+            Answer::Number(n) => json!(n),
+            Answer::Text(s) => json!(s),
+        };
+        let part = match self.part {
+            Part::One => 1,
+            Part::Two => 2,
+        };
+
+        json!({
+            "year": self.year,
+            "day": self.day,
+            "part": part,
+            "answer": answer,
+            "elapsed_ms": millis(self.timed.elapsed()),
+            "parse_ms": millis(self.timed.parse),
+            "solve_ms": millis(self.timed.solve),
+        })
+    }
+
+    /// The record printed in `format`
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_string(),
+            Format::Json => self.to_json().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} day {} part {}: {}",
+            self.year, self.day, self.part, self.timed.answer
+        )
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e3
+}
+
+/// The answers and timings of `records`, a row each, marking the slowest
+pub fn table(records: &[Record]) -> String {
+    let slowest = records
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, record)| record.timed.elapsed())
+        .map(|(i, _)| i);
+
+    let mut table = format!(
+        "{:<12} {:>4} {:>16} {:>10} {:>10} {:>10}\n",
+        "day", "part", "answer", "parse", "solve", "total"
+    );
+    for (i, record) in records.iter().enumerate() {
+        let row = format!(
+            "{:<12} {:>4} {:>16} {:>7.3} ms {:>7.3} ms {:>7.3} ms",
+            format!("{} day {}", record.year, record.day),
+            record.part.to_string(),
+            record.timed.answer.to_string(),
+            millis(record.timed.parse),
+            millis(record.timed.solve),
+            millis(record.timed.elapsed()),
+        );
+        table += &row;
+        if Some(i) == slowest {
+            table += "  <- slowest";
+        }
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(Ok(Format::Json), "json".parse());
+        assert_eq!(
+            Err("Unknown format xml, expected text or json".to_string()),
+            "xml".parse::<Format>()
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let mut record = record(3, Part::Two, 15678, 500, 1000);
+
+        assert_eq!("2019 day 3 part 2: 15678", record.format(Format::Text));
+        assert_eq!(
+            r#"{"answer":15678,"day":3,"elapsed_ms":1.5,"parse_ms":0.5,"part":2,"solve_ms":1.0,"year":2019}"#,
+            record.format(Format::Json)
+        );
+
+        record.timed.answer = Answer::Text("CJZLP".to_string());
+        assert_eq!(json!("CJZLP"), record.to_json()["answer"]);
+    }
+
+    #[test]
+    fn test_table() {
+        let table = table(&[
+            record(1, Part::One, 3212842, 20, 5),
+            record(5, Part::Two, 742621, 40, 2500),
+        ]);
+
+        assert_eq!(
+            vec![
+                "day          part           answer      parse      solve      total",
+                "2019 day 1      1          3212842   0.020 ms   0.005 ms   0.025 ms",
+                "2019 day 5      2           742621   0.040 ms   2.500 ms   2.540 ms  <- slowest",
+            ],
+            table.lines().collect::<Vec<_>>()
+        );
+    }
+
+    fn record(day: u32, part: Part, answer: i64, parse: u64, solve: u64) -> Record {
+        Record {
+            year: 2019,
+            day,
+            part,
+            timed: Timed {
+                answer: Answer::Number(answer),
+                parse: Duration::from_micros(parse),
+                solve: Duration::from_micros(solve),
+            },
+        }
+    }
+}
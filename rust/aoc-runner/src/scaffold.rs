@@ -0,0 +1,335 @@
+//! Creates the crate of a new day, registered with the workspace and the
+//! runner, from a skeleton of its solution.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, DocumentMut, InlineTable, Item, Value};
+
+/// The year of every day of the workspace, whose crates are named by day alone
+pub const YEAR: u32 = 2019;
+
+#[derive(Debug, PartialEq)]
+pub enum ScaffoldError {
+    Io(io::ErrorKind),
+    /// The day would clash with the day of the same number of `YEAR`
+    OtherYear(u32),
+    /// Puzzles are only released from the 1st to the 25th
+    InvalidDay(u32),
+    /// There is already a crate for the day
+    Exists(PathBuf),
+    /// A manifest or source of the workspace is not as expected
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScaffoldError::Io(kind) => write!(f, "{:?}", kind),
+            ScaffoldError::OtherYear(year) => write!(
+                f,
+                "Invalid year {}, the workspace only holds days of {}",
+                year, YEAR
+            ),
+            ScaffoldError::InvalidDay(day) => write!(f, "Invalid day {}, expected 1 to 25", day),
+            ScaffoldError::Exists(path) => write!(f, "{} already exists", path.display()),
+            ScaffoldError::Invalid(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl From<io::Error> for ScaffoldError {
+    fn from(e: io::Error) -> Self {
+        ScaffoldError::Io(e.kind())
+    }
+}
+
+/// Asking for a day that can't be created is a usage error
+impl From<ScaffoldError> for aoc::RunError {
+    fn from(e: ScaffoldError) -> Self {
+        match e {
+            ScaffoldError::OtherYear(_)
+            | ScaffoldError::InvalidDay(_)
+            | ScaffoldError::Exists(_) => aoc::RunError::Usage(e.to_string()),
+            _ => aoc::RunError::Input(e.to_string()),
+        }
+    }
+}
+
+pub type ScaffoldResult<T> = Result<T, ScaffoldError>;
+
+/// Creates the crate of `day` of `year` in `workspace`, returning the files
+/// that were created or changed
+pub fn scaffold(workspace: &Path, year: u32, day: u32) -> ScaffoldResult<Vec<PathBuf>> {
+    if year != YEAR {
+        return Err(ScaffoldError::OtherYear(year));
+    }
+    if !(1..=25).contains(&day) {
+        return Err(ScaffoldError::InvalidDay(day));
+    }
+
+    let name = format!("day{:02}", day);
+    let dir = workspace.join(&name);
+    if dir.exists() {
+        return Err(ScaffoldError::Exists(dir));
+    }
+
+    // Everything that can fail on an unexpected workspace is done before
+    // anything is written
+    let manifest = workspace.join("Cargo.toml");
+    let members = add_member(&manifest, &fs::read_to_string(&manifest)?, &name)?;
+    let runner_manifest = workspace.join("aoc-runner").join("Cargo.toml");
+    let runner_dependencies = add_dependency(
+        &runner_manifest,
+        &fs::read_to_string(&runner_manifest)?,
+        &name,
+    )?;
+    let runner_lib = workspace.join("aoc-runner").join("src").join("lib.rs");
+    let days = add_day(&runner_lib, &fs::read_to_string(&runner_lib)?, year, day)?;
+
+    let files = vec![
+        (dir.join("Cargo.toml"), package_manifest(&name)),
+        (dir.join("src").join("lib.rs"), solution(year, day)),
+        (dir.join("src").join("main.rs"), main(day)),
+        (dir.join("input.txt"), String::new()),
+        (manifest, members),
+        (runner_manifest, runner_dependencies),
+        (runner_lib, days),
+    ];
+
+    fs::create_dir_all(dir.join("src"))?;
+    for (path, contents) in &files {
+        fs::write(path, contents)?;
+    }
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+fn parse_manifest(path: &Path, text: &str) -> ScaffoldResult<DocumentMut> {
+    text.parse()
+        .map_err(|e| ScaffoldError::Invalid(path.into(), format!("{}", e).trim().to_string()))
+}
+
+/// Adds `name` to the members of the workspace, in order
+fn add_member(path: &Path, text: &str, name: &str) -> ScaffoldResult<String> {
+    let mut manifest = parse_manifest(path, text)?;
+    let members = manifest
+        .get_mut("workspace")
+        .and_then(|workspace| workspace.get_mut("members"))
+        .and_then(Item::as_array_mut)
+        .ok_or_else(|| ScaffoldError::Invalid(path.into(), "no workspace members".to_string()))?;
+
+    let index = members
+        .iter()
+        .position(|member| member.as_str().is_some_and(|member| member > name))
+        .unwrap_or(members.len());
+    members.insert(index, name);
+
+    Ok(manifest.to_string())
+}
+
+/// Makes the runner depend on the crate `name`
+fn add_dependency(path: &Path, text: &str, name: &str) -> ScaffoldResult<String> {
+    let mut manifest = parse_manifest(path, text)?;
+    let dependencies = manifest
+        .get_mut("dependencies")
+        .and_then(Item::as_table_mut)
+        .ok_or_else(|| ScaffoldError::Invalid(path.into(), "no dependencies".to_string()))?;
+
+    let mut dependency = InlineTable::new();
+    dependency.insert("path", Value::from(format!("../{}", name)));
+    dependencies.insert(name, value(dependency));
+    dependencies.sort_values();
+
+    Ok(manifest.to_string())
+}
+
+/// Registers the solution of the day last in `DAYS`
+fn add_day(path: &Path, text: &str, year: u32, day: u32) -> ScaffoldResult<String> {
+    let start = text
+        .find("pub const DAYS: &[Day] = &[\n")
+        .ok_or_else(|| ScaffoldError::Invalid(path.into(), "no DAYS".to_string()))?;
+    let end = start
+        + text[start..]
+            .find("\n];\n")
+            .ok_or_else(|| ScaffoldError::Invalid(path.into(), "no end of DAYS".to_string()))?;
+
+    Ok(format!(
+        "{}\n    Day {{\n        year: {},\n        day: {},\n        solution: &day{:02}::Day{:02},\n    }},{}",
+        &text[..end],
+        year,
+        day,
+        day,
+        day,
+        &text[end..]
+    ))
+}
+
+fn package_manifest(name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{}"
+version = "0.1.0"
+edition = "2018"
+
+# See more keys and their definitions at https://doc.rust-lang.org/cargo/reference/manifest.html
+
+[dependencies]
+aoc = {{ path = "../aoc" }}
+aoc-parse = {{ path = "../aoc-parse" }}
+"#,
+        name
+    )
+}
+
+fn solution(year: u32, day: u32) -> String {
+    format!(
+        r#"//! Day {day} of Advent of Code {year}
+//! https://adventofcode.com/{year}/day/{day}
+use aoc::{{Answer, Solution, SolveError, SolveResult}};
+
+pub struct Day{day:02};
+
+impl Solution for Day{day:02} {{
+    type Input = Vec<String>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Input> {{
+        Ok(aoc_parse::per_line(input)?)
+    }}
+
+    fn part1(&self, _input: &Self::Input) -> SolveResult<Answer> {{
+        Err(SolveError::NoAnswer("not solved yet".to_string()))
+    }}
+
+    fn part2(&self, _input: &Self::Input) -> SolveResult<Answer> {{
+        Err(SolveError::NoAnswer("not solved yet".to_string()))
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+
+    use super::*;
+
+    const EXAMPLE: &str = "";
+
+    #[test]
+    #[ignore = "fill in the example of the puzzle"]
+    fn test_part1_example() {{
+        let input = Day{day:02}.parse(EXAMPLE).unwrap();
+
+        assert_eq!(Ok(Answer::Number(0)), Day{day:02}.part1(&input));
+    }}
+
+    #[test]
+    #[ignore = "fill in the example of the puzzle"]
+    fn test_part2_example() {{
+        let input = Day{day:02}.parse(EXAMPLE).unwrap();
+
+        assert_eq!(Ok(Answer::Number(0)), Day{day:02}.part2(&input));
+    }}
+}}
+"#,
+        year = year,
+        day = day
+    )
+}
+
+fn main(day: u32) -> String {
+    format!(
+        "fn main() {{\n    aoc::main(day{:02}::Day{:02});\n}}\n",
+        day, day
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A workspace with the manifests and the registry of the runner
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = dir.path().join("aoc-runner");
+        fs::create_dir_all(runner.join("src")).unwrap();
+        fs::create_dir(dir.path().join("day01")).unwrap();
+
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"aoc\", \"day01\", \"intcode-diff\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            runner.join("Cargo.toml"),
+            "[package]\nname = \"aoc-runner\"\n\n[dependencies]\nday01 = { path = \"../day01\" }\ntoml = \"1.1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            runner.join("src").join("lib.rs"),
+            "pub const DAYS: &[Day] = &[\n    Day {\n        year: 2019,\n        day: 1,\n        solution: &day01::Day01,\n    },\n];\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn read(dir: &Path, path: &str) -> String {
+        fs::read_to_string(dir.join(path)).unwrap()
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let dir = workspace();
+
+        let files = scaffold(dir.path(), 2019, 6).unwrap();
+
+        assert_eq!(7, files.len());
+        assert_eq!(
+            "[workspace]\nmembers = [\"aoc\", \"day01\", \"day06\", \"intcode-diff\"]\n",
+            read(dir.path(), "Cargo.toml")
+        );
+        assert!(read(dir.path(), "aoc-runner/Cargo.toml")
+            .contains("day01 = { path = \"../day01\" }\nday06 = { path = \"../day06\" }\ntoml"));
+        assert!(read(dir.path(), "aoc-runner/src/lib.rs").ends_with(
+            "    Day {\n        year: 2019,\n        day: 6,\n        solution: &day06::Day06,\n    },\n];\n"
+        ));
+        assert!(read(dir.path(), "day06/src/lib.rs").contains("impl Solution for Day06 {"));
+        assert!(read(dir.path(), "day06/src/main.rs").contains("aoc::main(day06::Day06);"));
+        assert_eq!("", read(dir.path(), "day06/input.txt"));
+    }
+
+    #[test]
+    fn test_scaffold_refuses_existing_days() {
+        let dir = workspace();
+
+        assert_eq!(
+            Err(ScaffoldError::Exists(dir.path().join("day01"))),
+            scaffold(dir.path(), 2019, 1)
+        );
+        assert_eq!(
+            Err(ScaffoldError::InvalidDay(26)),
+            scaffold(dir.path(), 2019, 26)
+        );
+        assert_eq!(
+            Err(ScaffoldError::OtherYear(2020)),
+            scaffold(dir.path(), 2020, 1)
+        );
+        assert_eq!(
+            aoc::RunError::Usage(
+                "Invalid year 2020, the workspace only holds days of 2019".to_string()
+            ),
+            ScaffoldError::OtherYear(2020).into()
+        );
+    }
+
+    #[test]
+    fn test_scaffold_writes_nothing_on_an_unexpected_workspace() {
+        let dir = workspace();
+        fs::write(dir.path().join("aoc-runner/src/lib.rs"), "").unwrap();
+
+        assert!(matches!(
+            scaffold(dir.path(), 2019, 6),
+            Err(ScaffoldError::Invalid(..))
+        ));
+        assert!(!dir.path().join("day06").exists());
+        assert!(!read(dir.path(), "Cargo.toml").contains("day06"));
+    }
+}
@@ -0,0 +1,196 @@
+//! Checks the answers of the days against the ones stored in the
+//! `answers.toml` next to their input, such as
+//!
+//! ```toml
+//! part1 = 3212842
+//! part2 = "ABC"
+//! ```
+use super::inputs::Inputs;
+use super::Day;
+use aoc::{Answer, Part, SolveError};
+use std::fmt;
+use std::time::{Duration, Instant};
+use toml::{Table, Value};
+
+/// The expected answers of a day, where they are known
+#[derive(Debug, PartialEq, Default)]
+pub struct Answers {
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+impl Answers {
+    pub fn get(&self, part: Part) -> Option<&Answer> {
+        match part {
+            Part::One => self.part1.as_ref(),
+            Part::Two => self.part2.as_ref(),
+        }
+    }
+}
+
+pub fn parse_answers(text: &str) -> Result<Answers, String> {
+    let table = text.parse::<Table>().map_err(|e| e.message().to_string())?;
+
+    if let Some(key) = table.keys().find(|&key| key != "part1" && key != "part2") {
+        return Err(format!("Unknown key {}, expected part1 or part2", key));
+    }
+
+    let answer = |key: &str| match table.get(key) {
+        None => Ok(None),
+        Some(Value::Integer(n)) => Ok(Some(Answer::Number(*n))),
+        Some(Value::String(s)) => Ok(Some(Answer::Text(s.clone()))),
+        Some(value) => Err(format!(
+            "Invalid {} {}, expected a number or a string",
+            key, value
+        )),
+    };
+
+    Ok(Answers {
+        part1: answer("part1")?,
+        part2: answer("part2")?,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail {
+        expected: Answer,
+        actual: Answer,
+    },
+    Error(SolveError),
+    /// There is no input, or no expected answer, to check with
+    Missing(String),
+}
+
+/// The verification of a part of a day
+#[derive(Debug)]
+pub struct Check {
+    pub year: u32,
+    pub day: u32,
+    pub part: Part,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} day {} part {}: ", self.year, self.day, self.part)?;
+
+        match &self.status {
+            Status::Pass => write!(f, "pass")?,
+            Status::Fail { expected, actual } => {
+                write!(f, "FAIL, expected {} but got {}", expected, actual)?
+            }
+            Status::Error(e) => write!(f, "FAIL, {}", e)?,
+            Status::Missing(what) => return write!(f, "missing {}", what),
+        }
+        write!(f, " ({:.1} ms)", self.elapsed.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Solves both parts of `day`, checking them against its answers file
+pub fn verify(day: &Day, inputs: &Inputs) -> Vec<Check> {
+    let input = day.input(inputs);
+    let answers = match std::fs::read_to_string(day.answers_path()) {
+        Ok(text) => parse_answers(&text).map_err(|e| format!("answers ({})", e)),
+        Err(_) => Ok(Answers::default()),
+    };
+
+    Part::ALL
+        .iter()
+        .map(|&part| {
+            let mut elapsed = Duration::default();
+            let status = match (&input, &answers) {
+                (Err(e), _) => Status::Missing(format!("input ({})", e)),
+                (_, Err(e)) => Status::Missing(e.clone()),
+                (Ok(input), Ok(answers)) => match answers.get(part) {
+                    None => Status::Missing("answer".to_string()),
+                    Some(expected) => {
+                        let started = Instant::now();
+                        let result = day.solution.solve(part, input);
+                        elapsed = started.elapsed();
+
+                        match result {
+                            Ok(ref actual) if actual == expected => Status::Pass,
+                            Ok(actual) => Status::Fail {
+                                expected: expected.clone(),
+                                actual,
+                            },
+                            Err(e) => Status::Error(e),
+                        }
+                    }
+                },
+            };
+
+            Check {
+                year: day.year,
+                day: day.day,
+                part,
+                status,
+                elapsed,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::DAYS;
+
+    #[test]
+    fn test_parse_answers() {
+        assert_eq!(
+            Ok(Answers {
+                part1: Some(Answer::Number(-12)),
+                part2: Some(Answer::Text("ABC".to_string())),
+            }),
+            parse_answers("part1 = -12\npart2 = \"ABC\"\n")
+        );
+        assert_eq!(Ok(Answers::default()), parse_answers(""));
+        assert_eq!(
+            Err("Unknown key part3, expected part1 or part2".to_string()),
+            parse_answers("part3 = 1")
+        );
+        assert_eq!(
+            Err("Invalid part1 1.5, expected a number or a string".to_string()),
+            parse_answers("part1 = 1.5")
+        );
+        assert!(parse_answers("part1 =").is_err());
+    }
+
+    #[test]
+    fn test_display_check() {
+        let check = Check {
+            year: 2019,
+            day: 3,
+            part: Part::Two,
+            status: Status::Fail {
+                expected: Answer::Number(30),
+                actual: Answer::Number(6),
+            },
+            elapsed: Duration::from_micros(1500),
+        };
+        assert_eq!(
+            "2019 day 3 part 2: FAIL, expected 30 but got 6 (1.5 ms)",
+            check.to_string()
+        );
+
+        let check = Check {
+            status: Status::Missing("input".to_string()),
+            ..check
+        };
+        assert_eq!("2019 day 3 part 2: missing input", check.to_string());
+    }
+
+    #[test]
+    fn test_stored_answers_parse() {
+        for day in DAYS {
+            if let Ok(text) = std::fs::read_to_string(day.answers_path()) {
+                assert!(parse_answers(&text).is_ok(), "day {}", day.day);
+            }
+        }
+    }
+}
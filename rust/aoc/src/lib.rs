@@ -0,0 +1,378 @@
+//! What every day has in common, so that a single runner can solve any of
+//! them.
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: &'static [Part] = &[Part::One, Part::Two];
+}
+
+impl FromStr for Part {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(format!("Unknown part {}, expected 1 or 2", s)),
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::One => f.write_str("1"),
+            Part::Two => f.write_str("2"),
+        }
+    }
+}
+
+/// The answer to a part of a puzzle
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Answer::Number(i64::from(n))
+            }
+        })*
+    };
+}
+
+answer_from_number!(i32, i64, u32);
+
+/// Numbers that may not fit in an answer, which is no answer rather than a
+/// wrapped one
+macro_rules! answer_try_from_number {
+    ($($t:ty),*) => {
+        $(impl TryFrom<$t> for Answer {
+            type Error = SolveError;
+
+            fn try_from(n: $t) -> Result<Self, Self::Error> {
+                i64::try_from(n).map(Answer::Number).map_err(|_| {
+                    SolveError::NoAnswer(format!("{} is too large for an answer", n))
+                })
+            }
+        })*
+    };
+}
+
+answer_try_from_number!(u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolveError {
+    /// The puzzle input is not what the day expects
+    Input(String),
+    /// The input was understood, but has no answer
+    NoAnswer(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Input(message) => write!(f, "Invalid input: {}", message),
+            SolveError::NoAnswer(message) => write!(f, "No answer: {}", message),
+        }
+    }
+}
+
+impl From<aoc_parse::ParseError> for SolveError {
+    fn from(e: aoc_parse::ParseError) -> Self {
+        SolveError::Input(e.to_string())
+    }
+}
+
+pub type SolveResult<T> = Result<T, SolveError>;
+
+/// The solver of the puzzle of a day
+pub trait Solution {
+    /// The puzzle input, as both parts use it
+    type Input;
+
+    /// Parses `input`, the puzzle input as it was downloaded
+    fn parse(&self, input: &str) -> SolveResult<Self::Input>;
+
+    fn part1(&self, input: &Self::Input) -> SolveResult<Answer>;
+
+    fn part2(&self, input: &Self::Input) -> SolveResult<Answer>;
+}
+
+/// An answer, with how long it took to parse the input and to solve the part
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timed {
+    pub answer: Answer,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+impl Timed {
+    pub fn elapsed(&self) -> Duration {
+        self.parse + self.solve
+    }
+}
+
+/// A `Solution` solving a part from the raw input, so that the solutions of
+/// different days can be kept together and solved on any thread
+pub trait Solver: Sync {
+    /// Parses `input`, so that both parts can be solved from it
+    fn parse_input(&self, input: &str) -> SolveResult<Box<dyn Parsed + '_>>;
+
+    fn solve_timed(&self, part: Part, input: &str) -> SolveResult<Timed> {
+        let started = Instant::now();
+        let parsed = self.parse_input(input)?;
+        let parse = started.elapsed();
+
+        let started = Instant::now();
+        let answer = parsed.solve(part)?;
+
+        Ok(Timed {
+            answer,
+            parse,
+            solve: started.elapsed(),
+        })
+    }
+
+    fn solve(&self, part: Part, input: &str) -> SolveResult<Answer> {
+        self.solve_timed(part, input).map(|timed| timed.answer)
+    }
+}
+
+/// An input parsed by a `Solver`
+pub trait Parsed {
+    fn solve(&self, part: Part) -> SolveResult<Answer>;
+}
+
+struct ParsedInput<'a, S: Solution> {
+    solution: &'a S,
+    input: S::Input,
+}
+
+impl<S: Solution> Parsed for ParsedInput<'_, S> {
+    fn solve(&self, part: Part) -> SolveResult<Answer> {
+        match part {
+            Part::One => self.solution.part1(&self.input),
+            Part::Two => self.solution.part2(&self.input),
+        }
+    }
+}
+
+impl<S: Solution + Sync> Solver for S {
+    fn parse_input(&self, input: &str) -> SolveResult<Box<dyn Parsed + '_>> {
+        Ok(Box::new(ParsedInput {
+            solution: self,
+            input: self.parse(input)?,
+        }))
+    }
+}
+
+/// Why a binary failed, each kind exiting with its own code
+#[derive(Debug, PartialEq)]
+pub enum RunError {
+    /// The arguments are not what the binary expects
+    Usage(String),
+    /// The puzzle input, or another file, is missing or can't be understood
+    Input(String),
+    /// The input was understood, but could not be solved
+    Solver(String),
+}
+
+impl RunError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunError::Usage(_) => 2,
+            RunError::Input(_) => 3,
+            RunError::Solver(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Usage(message) | RunError::Input(message) | RunError::Solver(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl From<SolveError> for RunError {
+    fn from(e: SolveError) -> Self {
+        match e {
+            SolveError::Input(_) => RunError::Input(e.to_string()),
+            SolveError::NoAnswer(_) => RunError::Solver(e.to_string()),
+        }
+    }
+}
+
+pub type RunResult<T> = Result<T, RunError>;
+
+/// Exits with the code of the error of `result`, after printing its message
+pub fn exit_on_error(result: RunResult<()>) {
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+/// The argument at `index`, named `name` in the message if it is missing
+pub fn argument<'a>(args: &'a [String], index: usize, name: &str) -> RunResult<&'a str> {
+    args.get(index)
+        .map(String::as_str)
+        .ok_or_else(|| RunError::Usage(format!("Missing argument <{}>", name)))
+}
+
+/// The contents of the file at `path`, the argument named `name`
+pub fn read_file(path: Option<String>, name: &str) -> RunResult<String> {
+    let path = path.ok_or_else(|| RunError::Usage(format!("Missing argument <{}>", name)))?;
+
+    std::fs::read_to_string(&path)
+        .map_err(|e| RunError::Input(format!("Could not read {}: {}", path, e)))
+}
+
+/// Solves both parts of the puzzle for the input in the file given as the
+/// first argument, as the `main` of a day
+pub fn main<S: Solution>(solution: S) {
+    exit_on_error(run(solution, std::env::args().nth(1)));
+}
+
+fn run<S: Solution>(solution: S, path: Option<String>) -> RunResult<()> {
+    let input = solution.parse(&read_file(path, "input file")?)?;
+
+    for &part in Part::ALL {
+        let answer = match part {
+            Part::One => solution.part1(&input),
+            Part::Two => solution.part2(&input),
+        }?;
+        println!("Part {}: {}", part, answer);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_part() {
+        assert_eq!(Ok(Part::One), "1".parse());
+        assert_eq!(Ok(Part::Two), "2".parse());
+        assert_eq!(
+            Err("Unknown part 3, expected 1 or 2".to_string()),
+            "3".parse::<Part>()
+        );
+    }
+
+    #[test]
+    fn test_answer() {
+        assert_eq!(Ok(Answer::Number(12)), Answer::try_from(12usize));
+        assert_eq!(
+            Err(SolveError::NoAnswer(
+                "18446744073709551615 is too large for an answer".to_string()
+            )),
+            Answer::try_from(u64::MAX)
+        );
+        assert_eq!(Answer::Text("ABC".to_string()), "ABC".into());
+        assert_eq!("-3", Answer::from(-3).to_string());
+        assert_eq!("ABC", Answer::from("ABC").to_string());
+    }
+
+    struct Lengths;
+
+    impl Solution for Lengths {
+        type Input = Vec<usize>;
+
+        fn parse(&self, input: &str) -> SolveResult<Self::Input> {
+            Ok(input.lines().map(str::len).collect())
+        }
+
+        fn part1(&self, input: &Self::Input) -> SolveResult<Answer> {
+            Answer::try_from(input.iter().sum::<usize>())
+        }
+
+        fn part2(&self, input: &Self::Input) -> SolveResult<Answer> {
+            input
+                .iter()
+                .max()
+                .ok_or_else(|| SolveError::NoAnswer("No lines".to_string()))
+                .and_then(|&n| Answer::try_from(n))
+        }
+    }
+
+    #[test]
+    fn test_run_errors() {
+        assert_eq!(
+            Err(RunError::Usage("Missing argument <input file>".to_string())),
+            run(Lengths, None)
+        );
+
+        let e = run(Lengths, Some("does/not/exist".to_string())).unwrap_err();
+        assert_eq!(3, e.exit_code());
+        assert!(e.to_string().starts_with("Could not read does/not/exist: "));
+
+        assert_eq!(
+            RunError::Solver("No answer: No lines".to_string()),
+            Lengths.part2(&vec![]).unwrap_err().into()
+        );
+        assert_eq!(
+            3,
+            RunError::from(SolveError::Input("x".to_string())).exit_code()
+        );
+    }
+
+    #[test]
+    fn test_solver() {
+        let solver: &dyn Solver = &Lengths;
+
+        assert_eq!(Ok(Answer::Number(5)), solver.solve(Part::One, "ab\ncde"));
+        assert_eq!(Ok(Answer::Number(3)), solver.solve(Part::Two, "ab\ncde"));
+        assert_eq!(
+            Err(SolveError::NoAnswer("No lines".to_string())),
+            solver.solve(Part::Two, "")
+        );
+
+        let parsed = solver.parse_input("ab\ncde").unwrap();
+        assert_eq!(Ok(Answer::Number(5)), parsed.solve(Part::One));
+        assert_eq!(Ok(Answer::Number(3)), parsed.solve(Part::Two));
+
+        let timed = solver.solve_timed(Part::One, "ab\ncde").unwrap();
+        assert_eq!(Answer::Number(5), timed.answer);
+        assert_eq!(timed.parse + timed.solve, timed.elapsed());
+    }
+}
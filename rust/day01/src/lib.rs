@@ -0,0 +1,104 @@
+//! Day 1 of Advent of Code 2019
+//! https://adventofcode.com/2019/day/1
+use aoc::{Answer, Solution, SolveResult};
+use std::convert::TryFrom;
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Input = Vec<Mass>;
+
+    /// The input is the mass of a module on each line
+    fn parse(&self, input: &str) -> SolveResult<Self::Input> {
+        Ok(aoc_parse::per_line(input)?)
+    }
+
+    fn part1(&self, masses: &Self::Input) -> SolveResult<Answer> {
+        Answer::try_from(
+            masses
+                .iter()
+                .map(|&mass| u64::from(calculate_fuel_required_for_module(mass)))
+                .sum::<u64>(),
+        )
+    }
+
+    fn part2(&self, masses: &Self::Input) -> SolveResult<Answer> {
+        Answer::try_from(
+            masses
+                .iter()
+                .map(|&mass| u64::from(calculate_fuel_required_for_mass(mass)))
+                .sum::<u64>(),
+        )
+    }
+}
+
+pub type Mass = u32;
+pub type Fuel = u32;
+
+/// The fuel required for a module alone, not counting the mass of the fuel
+fn calculate_fuel_required_for_module(mass: Mass) -> Fuel {
+    (mass / 3).saturating_sub(2)
+}
+
+fn calculate_fuel_required_for_mass(mass: Mass) -> Fuel {
+    let fuel = calculate_fuel_required_for_module(mass);
+    fuel + calculate_fuel_required_for_fuel(fuel)
+}
+
+fn calculate_fuel_required_for_fuel(fuel: Fuel) -> Fuel {
+    match (fuel / 3).saturating_sub(2) {
+        0 => 0,
+        n => n + calculate_fuel_required_for_fuel(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use aoc::SolveError;
+
+    #[test]
+    fn test_calculate_fuel_required_for_mass() {
+        assert_eq!(2, calculate_fuel_required_for_mass(12));
+        assert_eq!(2, calculate_fuel_required_for_mass(14));
+        assert_eq!(966, calculate_fuel_required_for_mass(1969));
+        assert_eq!(50346, calculate_fuel_required_for_mass(100756));
+    }
+
+    #[test]
+    fn test_calculate_fuel_required_for_module() {
+        assert_eq!(2, calculate_fuel_required_for_module(12));
+        assert_eq!(654, calculate_fuel_required_for_module(1969));
+        assert_eq!(33583, calculate_fuel_required_for_module(100756));
+        assert_eq!(0, calculate_fuel_required_for_module(5));
+    }
+
+    #[test]
+    fn test_solution() {
+        let masses = Day01.parse("1969\n100756\n").unwrap();
+
+        assert_eq!(vec![1969, 100756], masses);
+        assert_eq!(Ok(Answer::Number(654 + 33583)), Day01.part1(&masses));
+        assert_eq!(Ok(Answer::Number(966 + 50346)), Day01.part2(&masses));
+        assert_eq!(
+            Ok(Answer::Number(2 * 1431655763)),
+            Day01.part1(&vec![u32::MAX, u32::MAX])
+        );
+        assert_eq!(
+            Err(SolveError::Input(
+                "line 2, column 1: invalid value \"x\": invalid digit found in string".to_string()
+            )),
+            Day01.parse("12\nx\n")
+        );
+    }
+
+    #[test]
+    fn test_calculate_fuel_required_for_fuel() {
+        assert_eq!(0, calculate_fuel_required_for_fuel(2));
+        assert_eq!(0, calculate_fuel_required_for_fuel(5));
+        assert_eq!(5, calculate_fuel_required_for_fuel(21));
+        assert_eq!(26, calculate_fuel_required_for_fuel(70));
+        assert_eq!(96, calculate_fuel_required_for_fuel(216));
+    }
+}
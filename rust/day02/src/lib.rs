@@ -0,0 +1,335 @@
+use aoc::{Answer, Solution, SolveError, SolveResult};
+
+/// The value the program should leave at address 0 in part 2
+pub const TARGET: u32 = 19690720;
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Input = Code;
+
+    /// The input is the program, as comma separated words
+    fn parse(&self, input: &str) -> SolveResult<Self::Input> {
+        let code: Code = aoc_parse::comma_separated(input)?;
+
+        if code.len() < 3 {
+            return Err(SolveError::Input(
+                "expected at least 3 words, for the opcode, noun and verb".to_string(),
+            ));
+        }
+        Ok(code)
+    }
+
+    /// The value left at address 0 after restoring the "1202 program alarm"
+    fn part1(&self, code: &Self::Input) -> SolveResult<Answer> {
+        run_with(code, 12, 2).map(Answer::from).ok_or_else(|| {
+            SolveError::NoAnswer("the program fails with noun 12 and verb 2".to_string())
+        })
+    }
+
+    fn part2(&self, code: &Self::Input) -> SolveResult<Answer> {
+        find_noun_verb(code, TARGET)
+            .map(|(noun, verb)| (100 * noun + verb).into())
+            .ok_or_else(|| SolveError::NoAnswer(format!("no noun and verb give {}", TARGET)))
+    }
+}
+
+/// Runs `code` with `noun` and `verb` at address 1 and 2, returning the value
+/// it halts with at address 0, or `None` if it fails
+pub fn run_with(code: &[u32], noun: u32, verb: u32) -> Option<u32> {
+    let mut code = code.to_vec();
+    *code.get_mut(1)? = noun;
+    *code.get_mut(2)? = verb;
+
+    let mut program = Program::new(code);
+
+    while !program.finished {
+        program.try_tick()?;
+    }
+
+    Some(program.code[0])
+}
+
+/// Finds the noun and verb, the values at address 1 and 2, with which `code`
+/// halts with `target` at address 0
+pub fn find_noun_verb(code: &[u32], target: u32) -> Option<(u32, u32)> {
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            if run_with(code, noun, verb) == Some(target) {
+                return Some((noun, verb));
+            }
+        }
+    }
+    None
+}
+
+pub type Pc = usize;
+pub type Code = Vec<u32>;
+
+#[derive(PartialEq, Debug)]
+pub struct Program {
+    pub pc: Pc,
+    pub code: Code,
+    pub finished: bool,
+}
+impl Program {
+    pub fn new(code: Code) -> Self {
+        Self {
+            code,
+            pc: 0,
+            finished: false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        let instr = decode_instr(&self.code[self.pc..]).unwrap();
+        self.execute_instr(instr);
+    }
+
+    /// Like `tick`, but returns `None` instead of panicking on an unknown
+    /// opcode, an address outside of the code or an overflow
+    pub fn try_tick(&mut self) -> Option<()> {
+        let code = self.code.get(self.pc..)?;
+        let instr = match decode_opcode(*code.first()?)? {
+            Opcode::Fin => Instr::Fin,
+            _ => decode_instr(code.get(..4)?)?,
+        };
+
+        let value = |addr: u32| self.code.get(addr as usize).copied();
+        match instr {
+            Instr::Add(a, b, t) => value(a)?.checked_add(value(b)?).and(value(t))?,
+            Instr::Mul(a, b, t) => value(a)?.checked_mul(value(b)?).and(value(t))?,
+            Instr::Fin => 0,
+        };
+
+        self.execute_instr(instr);
+        Some(())
+    }
+
+    fn execute_instr(&mut self, instr: Instr) {
+        use Instr::*;
+
+        match instr {
+            Add(a, b, t) => {
+                self.code[t as usize] = self.code[a as usize] + self.code[b as usize];
+                self.pc += 4;
+            }
+            Mul(a, b, t) => {
+                self.code[t as usize] = self.code[a as usize] * self.code[b as usize];
+                self.pc += 4;
+            }
+            Fin => self.finished = true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Opcode {
+    Add,
+    Mul,
+    Fin,
+}
+
+#[derive(Debug, PartialEq)]
+enum Instr {
+    Add(u32, u32, u32),
+    Mul(u32, u32, u32),
+    Fin,
+}
+
+fn decode_opcode(i: u32) -> Option<Opcode> {
+    match i {
+        1 => Some(Opcode::Add),
+        2 => Some(Opcode::Mul),
+        99 => Some(Opcode::Fin),
+        _ => None,
+    }
+}
+
+fn decode_instr(code: &[u32]) -> Option<Instr> {
+    match decode_opcode(code[0])? {
+        Opcode::Add => Some(Instr::Add(code[1], code[2], code[3])),
+        Opcode::Mul => Some(Instr::Mul(code[1], code[2], code[3])),
+        Opcode::Fin => Some(Instr::Fin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_decode_opcode() {
+        assert_eq!(Some(Opcode::Add), decode_opcode(1));
+        assert_eq!(Some(Opcode::Mul), decode_opcode(2));
+        assert_eq!(Some(Opcode::Fin), decode_opcode(99));
+        assert_eq!(None, decode_opcode(5));
+    }
+
+    #[test]
+    fn test_decode_instr() {
+        assert_eq!(Some(Instr::Add(2, 3, 4)), decode_instr(&[1, 2, 3, 4]));
+        assert_eq!(Some(Instr::Mul(3, 4, 5)), decode_instr(&[2, 3, 4, 5]));
+        assert_eq!(Some(Instr::Fin), decode_instr(&[99]));
+    }
+
+    #[test]
+    fn test_execute_instr_add() {
+        let mut program = Program::new(vec![0, 1, 2, 0]);
+
+        program.execute_instr(Instr::Add(1, 2, 0));
+
+        assert_eq!(
+            Program {
+                code: vec![3, 1, 2, 0],
+                pc: 4,
+                finished: false
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_mul() {
+        let mut program = Program::new(vec![0, 4, 5, 0, 5, 6]);
+
+        program.execute_instr(Instr::Mul(4, 5, 0));
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![30, 4, 5, 0, 5, 6],
+                finished: false
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_fin() {
+        let mut program = Program::new(vec![99]);
+
+        program.execute_instr(Instr::Fin);
+
+        assert_eq!(
+            Program {
+                code: vec![99],
+                pc: 0,
+                finished: true
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        let expected = vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50];
+        let mut program = Program::new(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+
+        program.tick();
+        program.tick();
+        program.tick();
+
+        assert_eq!(
+            Program {
+                code: expected,
+                pc: 8,
+                finished: true
+            },
+            program
+        );
+    }
+}
+
+#[cfg(test)]
+mod solution_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_find_noun_verb() {
+        // ADD [noun], [verb] -> [0]; FIN, followed by words holding their
+        // own address
+        let mut code = (0..100).collect::<Vec<u32>>();
+        code[..5].copy_from_slice(&[1, 0, 0, 0, 99]);
+
+        assert_eq!(Some((0, 5)), find_noun_verb(&code, 6));
+        assert_eq!(None, find_noun_verb(&code, 200));
+    }
+
+    #[test]
+    fn test_solution() {
+        let mut words = (0..100).map(|i| i.to_string()).collect::<Vec<_>>();
+        words[..5].clone_from_slice(&["1", "0", "0", "0", "99"].map(String::from));
+        let code = Day02.parse(&words.join(",")).unwrap();
+
+        assert_eq!(Ok(Answer::Number(12 + 2)), Day02.part1(&code));
+        assert_eq!(
+            Err(SolveError::NoAnswer(
+                "no noun and verb give 19690720".to_string()
+            )),
+            Day02.part2(&code)
+        );
+        assert_eq!(
+            Err(SolveError::Input(
+                "line 1, column 3: invalid value \"x\": invalid digit found in string".to_string()
+            )),
+            Day02.parse("1,x,0,0")
+        );
+        assert!(Day02.parse("99").is_err());
+        assert_eq!(
+            Err(SolveError::NoAnswer(
+                "the program fails with noun 12 and verb 2".to_string()
+            )),
+            Day02.part1(&vec![1, 0, 0, 0, 99])
+        );
+    }
+
+    #[test]
+    fn test_try_tick() {
+        let fails = |code: Vec<u32>| Program::new(code).try_tick().is_none();
+
+        assert!(fails(vec![1, 0, 0, 4]));
+        assert!(fails(vec![1, 0, 0]));
+        assert!(fails(vec![5, 0, 0, 0]));
+        assert!(fails(vec![2, 4, 4, 0, u32::MAX]));
+        assert!(!fails(vec![1, 0, 0, 0]));
+
+        let mut program = Program::new(vec![2, 0, 0, 0, 99]);
+        assert_eq!(Some(()), program.try_tick());
+        assert_eq!(Some(()), program.try_tick());
+        assert!(program.finished);
+        assert_eq!(4, program.code[0]);
+    }
+}
+
+#[cfg(test)]
+mod conformance_tests {
+
+    use super::*;
+    use intcode_conformance::{Engine, Feature, Outcome, Word};
+
+    struct Day02;
+    impl Engine for Day02 {
+        const FEATURES: &'static [Feature] = &[];
+
+        fn run(image: &[Word], _input: &[Word]) -> Outcome {
+            let mut program = Program::new(image.iter().map(|&w| w as u32).collect());
+
+            while !program.finished {
+                program.tick();
+            }
+
+            Outcome {
+                output: vec![],
+                memory: program.code.iter().map(|&w| w.into()).collect(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_conformance() {
+        assert_eq!(5, intcode_conformance::check::<Day02>());
+    }
+}
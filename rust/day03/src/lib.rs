@@ -0,0 +1,518 @@
+use aoc::{Answer, Solution, SolveError, SolveResult};
+use std::str::FromStr;
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    type Input = Wires;
+
+    /// The input is the path of a wire on each of two lines
+    fn parse(&self, input: &str) -> SolveResult<Self::Input> {
+        match &aoc_parse::comma_separated_lines::<Command>(input)?[..] {
+            [wire1, wire2] => {
+                check_wire(wire1)?;
+                check_wire(wire2)?;
+                Ok(Wires::new(wire1, wire2))
+            }
+            wires => Err(SolveError::Input(format!(
+                "expected the paths of two wires, got {}",
+                wires.len()
+            ))),
+        }
+    }
+
+    fn part1(&self, wires: &Self::Input) -> SolveResult<Answer> {
+        wires
+            .shortest_path()
+            .map(|(_, distance)| distance.into())
+            .ok_or_else(no_intersection)
+    }
+
+    fn part2(&self, wires: &Self::Input) -> SolveResult<Answer> {
+        wires
+            .least_steps_path()
+            .map(Answer::from)
+            .ok_or_else(no_intersection)
+    }
+}
+
+/// The most commands of a wire, as the path is built recursively
+const MAX_COMMANDS: usize = 10_000;
+/// The farthest a wire may go from the central port, so that the lengths of
+/// the wires fit in 32 bits
+const MAX_DISTANCE: i64 = 100_000;
+
+fn check_wire(wire: &[Command]) -> SolveResult<()> {
+    if wire.len() > MAX_COMMANDS {
+        return Err(SolveError::Input(format!(
+            "a wire has {} commands, at most {} are supported",
+            wire.len(),
+            MAX_COMMANDS
+        )));
+    }
+
+    let (mut x, mut y) = (0i64, 0i64);
+    for Command(direction, distance) in wire {
+        let distance = i64::from(*distance);
+        match direction {
+            Direction::Left => x -= distance,
+            Direction::Right => x += distance,
+            Direction::Up => y += distance,
+            Direction::Down => y -= distance,
+        }
+        if x.abs().max(y.abs()) > MAX_DISTANCE {
+            return Err(SolveError::Input(format!(
+                "a wire goes farther than {} from the central port",
+                MAX_DISTANCE
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn no_intersection() -> SolveError {
+    SolveError::NoAnswer("the wires never cross".to_string())
+}
+
+/// The lines laid by two wires
+#[derive(Debug, PartialEq)]
+pub struct Wires(Vec<Line>, Vec<Line>);
+
+impl Wires {
+    fn new(wire1: &[Command], wire2: &[Command]) -> Self {
+        let path1 = commands_to_path(wire1, Point::ZERO);
+        let path2 = commands_to_path(wire2, Point::ZERO);
+
+        Wires(points_to_lines(&path1), points_to_lines(&path2))
+    }
+
+    /// The intersection closest to the central port, with its distance
+    pub fn shortest_path(&self) -> Option<(Point, u32)> {
+        let mut intersecting_points = intersecting_points(&self.0, &self.1)
+            .iter()
+            .map(|(_, p)| (*p, p.manhattan_distance_to(&Point::ZERO)))
+            .collect::<Vec<_>>();
+        intersecting_points.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+        intersecting_points.reverse();
+
+        intersecting_points.pop()
+    }
+
+    /// The fewest combined steps the wires take to reach an intersection
+    pub fn least_steps_path(&self) -> Option<u32> {
+        let mut intersecting_points = intersecting_points(&self.0, &self.1)
+            .iter()
+            .map(|((lines1, lines2), ..)| lines1.length() + lines2.length())
+            .collect::<Vec<_>>();
+        intersecting_points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        intersecting_points.reverse();
+        intersecting_points.pop()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+type Distance = u32;
+#[derive(PartialEq, Debug)]
+struct Command(Direction, Distance);
+#[derive(PartialEq, Debug, Clone)]
+struct Lines(Vec<Line>);
+impl Lines {
+    fn length(&self) -> u32 {
+        self.0.iter().fold(0, |acc, x| acc + x.length())
+    }
+}
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Point(i32, i32);
+impl Point {
+    const ZERO: Point = Point(0, 0);
+
+    fn to_i64(self) -> (i64, i64) {
+        (i64::from(self.0), i64::from(self.1))
+    }
+
+    fn manhattan_distance_to(&self, other: &Point) -> u32 {
+        ((other.0 - self.0).abs() + (other.1 - self.1).abs()) as u32
+    }
+}
+type Path = Vec<Point>;
+#[derive(Debug, PartialEq, Clone)]
+struct Line(Point, Point);
+impl Line {
+    /// Intersection implemented as per https://en.m.wikipedia.org/wiki/Line–line_intersection#Given_two_points_on_each_line
+    fn intersects(&self, other: &Line) -> Option<Point> {
+        // In 64 bits, since the products overflow 32 bits for points far
+        // from the central port
+        let (x1, y1) = self.0.to_i64();
+        let (x2, y2) = self.1.to_i64();
+        let (x3, y3) = other.0.to_i64();
+        let (x4, y4) = other.1.to_i64();
+
+        let tnn = (x1 - x3) * (y3 - y4) - (y1 - y3) * (x3 - x4);
+        let tdn = (x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4);
+        let unn = (x1 - x2) * (y1 - y3) - (y1 - y2) * (x1 - x3);
+
+        if tdn == 0 || tnn == 0 || unn == 0 {
+            None
+        } else {
+            let t = tnn as f64 / tdn as f64;
+            let u = -unn as f64 / tdn as f64;
+
+            // println!("t: {}, u: {}", t, u);
+
+            if !((0f64 < t && t < 1f64) && (0f64 < u && u < 1f64)) {
+                return None;
+            }
+
+            let px = x1 + (t * (x2 - x1) as f64) as i64;
+            let py = y1 + (t * (y2 - y1) as f64) as i64;
+
+            //println!("L1: {:?}, L2: {:?}", self, other);
+
+            Some(Point(px as i32, py as i32))
+        }
+    }
+
+    fn length(&self) -> u32 {
+        (((self.1).0 - (self.0).0).abs() + ((self.1).1 - (self.0).1).abs()) as u32
+    }
+}
+
+/// A command such as `R8`
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let direction = match s.chars().next() {
+            Some(c) if "LRUD".contains(c) => parse_direction(c),
+            _ => return Err("expected a direction L, R, U or D".to_string()),
+        };
+        let distance = s[1..]
+            .parse()
+            .map_err(|_| "expected a distance after the direction".to_string())?;
+
+        Ok(Command(direction, distance))
+    }
+}
+
+fn parse_direction(c: char) -> Direction {
+    match c {
+        'L' => Direction::Left,
+        'R' => Direction::Right,
+        'U' => Direction::Up,
+        'D' => Direction::Down,
+        c => panic!("Unparsable: {}", c),
+    }
+}
+
+fn commands_to_path(commands: &[Command], p: Point) -> Path {
+    let path = vec![p];
+
+    if commands.is_empty() {
+        return path;
+    }
+
+    let Point(px, py) = p;
+    let p2 = match commands[0] {
+        Command(Direction::Left, x) => Point(px - x as i32, py),
+        Command(Direction::Right, x) => Point(px + x as i32, py),
+        Command(Direction::Up, y) => Point(px, py + y as i32),
+        Command(Direction::Down, y) => Point(px, py - y as i32),
+    };
+
+    [&path[..], &commands_to_path(&commands[1..], p2)[..]].concat()
+}
+
+fn points_to_lines(points: &[Point]) -> Vec<Line> {
+    match (points.first(), points.get(1)) {
+        (Some(p1), Some(p2)) => [&[Line(*p1, *p2)], &points_to_lines(&points[1..])[..]].concat(),
+        _ => Vec::new(),
+    }
+}
+
+fn intersecting_points(lines1: &Vec<Line>, lines2: &Vec<Line>) -> Vec<((Lines, Lines), Point)> {
+    let mut vr = vec![];
+    let mut v1 = vec![];
+    for line1 in lines1 {
+        let mut v2 = vec![];
+        for line2 in lines2 {
+            if let Some(p) = line1.intersects(line2) {
+                let mut lines1 = Lines(v1.iter().cloned().cloned().collect());
+                lines1.0.push(Line(line1.0, p));
+                let mut lines2 = Lines(v2.iter().cloned().cloned().collect());
+                lines2.0.push(Line(line2.0, p));
+                vr.push(((lines1, lines2), p));
+            }
+            v2.push(line2);
+        }
+        v1.push(line1);
+    }
+    vr
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!(Right, parse_direction('R'));
+        assert_eq!(Left, parse_direction('L'));
+        assert_eq!(Up, parse_direction('U'));
+        assert_eq!(Down, parse_direction('D'));
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(Ok(Command(Right, 10)), "R10".parse());
+        assert_eq!(Ok(Command(Up, 9)), "U9".parse());
+        assert_eq!(Ok(Command(Down, 999)), "D999".parse());
+        assert_eq!(Ok(Command(Left, 7)), "L7".parse());
+        assert_eq!(
+            Err("expected a distance after the direction".to_string()),
+            "R".parse::<Command>()
+        );
+    }
+
+    #[test]
+    fn test_commands_to_path() {
+        let path = commands_to_path(
+            &[Command(Up, 10), Command(Right, 9), Command(Down, 1)],
+            Point::ZERO,
+        );
+
+        assert_eq!(
+            vec![Point(0, 0), Point(0, 10), Point(9, 10), Point(9, 9)],
+            path
+        );
+    }
+
+    #[test]
+    fn test_points_to_lines() {
+        assert_eq!(
+            vec![
+                Line(Point(1, 1), Point(2, 2)),
+                Line(Point(2, 2), Point(3, 4))
+            ],
+            points_to_lines(&[Point(1, 1), Point(2, 2), Point(3, 4)])
+        );
+    }
+
+    #[test]
+    fn test_line_intersects() {
+        assert_eq!(
+            Some(Point(0, 0)),
+            Line(Point(0, -1), Point(0, 1)).intersects(&Line(Point(-1, 0), Point(1, 0)))
+        );
+        assert_eq!(
+            None,
+            Line(Point(1, 0), Point(1, 2)).intersects(&Line(Point(2, 0), Point(2, 2)))
+        );
+        assert_eq!(
+            None,
+            Line(Point(0, 0), Point(0, 7)).intersects(&Line(Point(0, 0), Point(8, 0)))
+        );
+        assert_eq!(
+            None,
+            Line(Point(0, 0), Point(8, 0)).intersects(&Line(Point(6, 7), Point(6, 3)))
+        );
+        assert_eq!(
+            None,
+            Line(Point(8, 0), Point(8, 5)).intersects(&Line(Point(6, 3), Point(2, 3)))
+        );
+    }
+
+    #[test]
+    fn test_example1() {
+        let s = "R8,U5,L5,D3\nU7,R6,D4,L4\n";
+        let wire1 = [
+            Command(Right, 8),
+            Command(Up, 5),
+            Command(Left, 5),
+            Command(Down, 3),
+        ];
+        let wire2 = [
+            Command(Up, 7),
+            Command(Right, 6),
+            Command(Down, 4),
+            Command(Left, 4),
+        ];
+
+        let path1 = commands_to_path(&wire1, Point::ZERO);
+        let path2 = commands_to_path(&wire2, Point::ZERO);
+        assert_eq!(
+            vec![
+                Point(0, 0),
+                Point(8, 0),
+                Point(8, 5),
+                Point(3, 5),
+                Point(3, 2)
+            ],
+            path1
+        );
+        assert_eq!(
+            vec![
+                Point(0, 0),
+                Point(0, 7),
+                Point(6, 7),
+                Point(6, 3),
+                Point(2, 3)
+            ],
+            path2
+        );
+
+        let lines1 = points_to_lines(&path1);
+        let lines2 = points_to_lines(&path2);
+        assert_eq!(
+            vec![
+                Line(Point(0, 0), Point(8, 0)),
+                Line(Point(8, 0), Point(8, 5)),
+                Line(Point(8, 5), Point(3, 5)),
+                Line(Point(3, 5), Point(3, 2))
+            ],
+            lines1
+        );
+        assert_eq!(
+            vec![
+                Line(Point(0, 0), Point(0, 7)),
+                Line(Point(0, 7), Point(6, 7)),
+                Line(Point(6, 7), Point(6, 3)),
+                Line(Point(6, 3), Point(2, 3))
+            ],
+            lines2
+        );
+        assert_eq!(
+            Wires(lines1.clone(), lines2.clone()),
+            Day03.parse(s).unwrap()
+        );
+
+        let intersecting_points = intersecting_points(&lines1, &lines2);
+        assert_eq!(
+            vec![
+                (
+                    (
+                        Lines(vec![
+                            Line(Point(0, 0), Point(8, 0)),
+                            Line(Point(8, 0), Point(8, 5)),
+                            Line(Point(8, 5), Point(6, 5))
+                        ]),
+                        Lines(vec![
+                            Line(Point(0, 0), Point(0, 7)),
+                            Line(Point(0, 7), Point(6, 7)),
+                            Line(Point(6, 7), Point(6, 5)),
+                        ])
+                    ),
+                    Point(6, 5)
+                ),
+                (
+                    (
+                        Lines(vec![
+                            Line(Point(0, 0), Point(8, 0)),
+                            Line(Point(8, 0), Point(8, 5)),
+                            Line(Point(8, 5), Point(3, 5)),
+                            Line(Point(3, 5), Point(3, 3))
+                        ]),
+                        Lines(vec![
+                            Line(Point(0, 0), Point(0, 7)),
+                            Line(Point(0, 7), Point(6, 7)),
+                            Line(Point(6, 7), Point(6, 3)),
+                            Line(Point(6, 3), Point(3, 3))
+                        ])
+                    ),
+                    Point(3, 3)
+                ),
+            ],
+            intersecting_points
+        );
+    }
+
+    #[test]
+    fn test_example2() {
+        let s = "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83\n";
+
+        let (_, x) = Day03.parse(s).unwrap().shortest_path().unwrap();
+
+        assert_eq!(159, x);
+    }
+
+    #[test]
+    fn test_example3() {
+        let s =
+            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7\n";
+
+        let (_, x) = Day03.parse(s).unwrap().shortest_path().unwrap();
+
+        assert_eq!(135, x);
+    }
+
+    #[test]
+    fn test_least_steps_path_example1() {
+        let s = "R8,U5,L5,D3\nU7,R6,D4,L4\n";
+
+        let x = Day03.parse(s).unwrap().least_steps_path().unwrap();
+
+        assert_eq!(x, 30);
+    }
+
+    #[test]
+    fn test_least_steps_path_example2() {
+        let s = "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83\n";
+
+        let x = Day03.parse(s).unwrap().least_steps_path().unwrap();
+
+        assert_eq!(x, 610);
+    }
+
+    #[test]
+    fn test_least_steps_path_example3() {
+        let s =
+            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7\n";
+
+        let x = Day03.parse(s).unwrap().least_steps_path().unwrap();
+
+        assert_eq!(x, 410);
+    }
+
+    #[test]
+    fn test_solution() {
+        let s = "R8,U5,L5,D3\nU7,R6,D4,L4\n";
+
+        let wires = Day03.parse(s).unwrap();
+
+        assert_eq!(Ok(Answer::Number(6)), Day03.part1(&wires));
+        assert_eq!(Ok(Answer::Number(30)), Day03.part2(&wires));
+        assert_eq!(
+            Err(SolveError::Input(
+                "expected the paths of two wires, got 1".to_string()
+            )),
+            Day03.parse("R8,U5,L5,D3\n")
+        );
+        assert_eq!(
+            Err(SolveError::Input(
+                "line 2, column 4: invalid value \"X6\": expected a direction L, R, U or D"
+                    .to_string()
+            )),
+            Day03.parse("R8,U5\nU7,X6\n")
+        );
+    }
+
+    #[test]
+    fn test_far_wires() {
+        let wires = Day03.parse("R90000,U90000\nU50000,R95000\n").unwrap();
+        assert_eq!(Ok(Answer::Number(140000)), Day03.part1(&wires));
+
+        assert_eq!(
+            Err(SolveError::Input(
+                "a wire goes farther than 100000 from the central port".to_string()
+            )),
+            Day03.parse("R90000,R20000\nU5\n")
+        );
+    }
+}
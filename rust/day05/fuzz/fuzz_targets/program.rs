@@ -0,0 +1,27 @@
+//! Feeds arbitrary images and input to `Program`, which must report an error
+//! rather than panic. Run with `cargo fuzz run program` from `day05`.
+#![no_main]
+use day05::{Limits, Program, ProgramError, Word};
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+fuzz_target!(|data: (Vec<Word>, String)| {
+    let (code, input) = data;
+    let mut program = Program::new(code);
+
+    // The image may well loop forever
+    let limits = Limits {
+        max_steps: Some(10_000),
+        ..Limits::default()
+    };
+
+    let result = program.run(
+        &mut Cursor::new(input),
+        &mut Cursor::new(Vec::new()),
+        &limits,
+    );
+
+    if let Err(ProgramError::Io(kind)) = result {
+        panic!("Unexpected I/O error: {:?}", kind);
+    }
+});
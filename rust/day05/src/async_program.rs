@@ -0,0 +1,138 @@
+//! An async variant of `Program::run`, so that several machines feeding each
+//! other can share a single-threaded executor instead of being ticked round
+//! robin.
+use super::*;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use std::io;
+
+impl Program {
+    /// Runs the program until it finishes or goes beyond one of `limits`.
+    /// `In` awaits the next value from `input` and `Out` sends its value to
+    /// `output`.
+    pub async fn run_async<I, O>(
+        &mut self,
+        mut input: I,
+        mut output: O,
+        limits: &Limits,
+    ) -> ProgramResult<()>
+    where
+        I: Stream<Item = Word> + Unpin,
+        O: Sink<Word> + Unpin,
+    {
+        let mut budget = Budget::new(limits);
+        self.max_memory = limits.max_memory;
+
+        while !self.finished {
+            let instr = budget.next(self)?;
+            let size = instr.size();
+
+            match instr {
+                Instr(opcode, params) if opcode.number == opcodes::IN => {
+                    let value = input.next().await.ok_or(ProgramError::InputClosed)?;
+
+                    self.write(self.address(opcode.modes[0], params[0])?, value)?;
+                    self.pc += size;
+                }
+                Instr(opcode, params) if opcode.number == opcodes::OUT => {
+                    let value = self.param_value(opcode.modes[0], params[0])?;
+
+                    output
+                        .send(value)
+                        .await
+                        .map_err(|_| ProgramError::OutputClosed)?;
+                    self.pc += size;
+                }
+                instr => self.execute_instr(instr, &mut io::empty(), &mut io::sink())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use futures::channel::mpsc;
+    use futures::executor::{block_on, LocalPool};
+    use futures::task::LocalSpawnExt;
+
+    /// Reads a value and outputs it plus one
+    const INCREMENT: &[Word] = &[3, 0, 1001, 0, 1, 0, 4, 0, 99];
+
+    #[test]
+    fn test_run_async() {
+        let mut program = Program::new(vec![3, 0, 4, 0, 99]);
+        let (tx, rx) = mpsc::unbounded();
+
+        block_on(program.run_async(futures::stream::iter(vec![42]), tx, &Limits::default()))
+            .unwrap();
+
+        assert_eq!(vec![42], block_on(rx.collect::<Vec<_>>()));
+        assert!(program.finished);
+    }
+
+    #[test]
+    fn test_run_async_chain() {
+        let mut pool = LocalPool::new();
+        let spawner = pool.spawner();
+
+        let (first_tx, mut rx) = mpsc::unbounded();
+        for _ in 0..5 {
+            let (tx, next_rx) = mpsc::unbounded();
+            let mut program = Program::new(INCREMENT.to_vec());
+
+            spawner
+                .spawn_local(
+                    async move { program.run_async(rx, tx, &Limits::default()).await.unwrap() },
+                )
+                .unwrap();
+            rx = next_rx;
+        }
+
+        first_tx.unbounded_send(0).unwrap();
+        pool.run();
+
+        assert_eq!(vec![5], block_on(rx.collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn test_run_async_limits() {
+        // JT #1, #0 loops forever
+        let mut program = Program::new(vec![1105, 1, 0]);
+        let (tx, _rx) = mpsc::unbounded();
+        let limits = Limits {
+            max_steps: Some(10),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Err(ProgramError::StepLimitExceeded(10)),
+            block_on(program.run_async(futures::stream::empty(), tx, &limits))
+        );
+    }
+
+    #[test]
+    fn test_run_async_input_closed() {
+        let mut program = Program::new(INCREMENT.to_vec());
+        let (tx, _rx) = mpsc::unbounded();
+
+        assert_eq!(
+            Err(ProgramError::InputClosed),
+            block_on(program.run_async(futures::stream::empty(), tx, &Limits::default()))
+        );
+    }
+
+    #[test]
+    fn test_run_async_output_closed() {
+        let mut program = Program::new(INCREMENT.to_vec());
+        let (tx, rx) = mpsc::unbounded();
+        drop(rx);
+
+        assert_eq!(
+            Err(ProgramError::OutputClosed),
+            block_on(program.run_async(futures::stream::iter(vec![1]), tx, &Limits::default()))
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use aoc::{argument, exit_on_error, RunError, RunResult};
+use day05::coverage::Coverage;
+use day05::loader::{self, Source};
+use day05::{Limits, Program};
+
+/// Runs an image like `day05` does and then writes its coverage to stderr,
+/// as a listing or, with `--lcov`, as an LCOV tracefile. The run can be
+/// bounded with the options of `Limits::set_option`.
+fn main() {
+    exit_on_error(run(&std::env::args().skip(1).collect::<Vec<_>>()));
+}
+
+fn run(args: &[String]) -> RunResult<()> {
+    let name = argument(args, 0, "image")?;
+    let mut lcov = false;
+    let mut limits = Limits::default();
+
+    let mut options = args[1..].iter().map(String::as_str);
+    while let Some(option) = options.next() {
+        if option == "--lcov" {
+            lcov = true;
+            continue;
+        }
+        let value = options
+            .next()
+            .ok_or_else(|| RunError::Usage(format!("Missing value of {}", option)))?;
+        if !limits.set_option(option, value).map_err(RunError::Usage)? {
+            return Err(RunError::Usage(format!("Unknown option {}", option)));
+        }
+    }
+
+    let code = loader::load(&Source::from(name))?;
+    let mut program = Program::new(code);
+    let mut coverage = Coverage::new();
+
+    let result = program.run_with(
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+        &limits,
+        |program, instr| coverage.record(program, instr),
+    );
+
+    if lcov {
+        eprint!("{}", coverage.lcov(name, &program.code));
+    } else {
+        eprint!("{}", coverage.listing(&program.code));
+    }
+    Ok(result?)
+}
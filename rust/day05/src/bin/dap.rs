@@ -0,0 +1,9 @@
+use aoc::{exit_on_error, RunError};
+
+/// Serves the Debug Adapter Protocol on stdio
+fn main() {
+    exit_on_error(
+        day05::dap::serve(std::io::stdin().lock(), std::io::stdout())
+            .map_err(|e| RunError::Input(format!("Could not serve: {}", e))),
+    );
+}
@@ -0,0 +1,18 @@
+use aoc::{argument, exit_on_error, RunResult};
+use day05::decompile::decompile;
+use day05::loader::{self, Source};
+
+fn main() {
+    exit_on_error(run(&std::env::args().skip(1).collect::<Vec<_>>()));
+}
+
+fn run(args: &[String]) -> RunResult<()> {
+    let code = loader::load(&Source::from(argument(args, 0, "image")?))?;
+
+    let functions = decompile(&code)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    println!("{}", functions.join("\n\n"));
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use aoc::{argument, exit_on_error, RunError, RunResult};
+use day05::loader::{self, Source};
+use day05::wasm;
+use day05::Dialect;
+
+/// Compiles the image given as the first argument to a WebAssembly module
+/// written to the second, for the dialect `day02`, `day05` (the default) or
+/// `day09` given as the third.
+fn main() {
+    exit_on_error(run(&std::env::args().skip(1).collect::<Vec<_>>()));
+}
+
+fn run(args: &[String]) -> RunResult<()> {
+    let source = Source::from(argument(args, 0, "image")?);
+    let path = argument(args, 1, "module")?;
+    let dialect = match args.get(2).map(String::as_str) {
+        Some("day02") => Dialect::DAY02,
+        None | Some("day05") => Dialect::DAY05,
+        Some("day09") => Dialect::DAY09,
+        Some(dialect) => return Err(RunError::Usage(format!("Unknown dialect {}", dialect))),
+    };
+
+    let code = loader::load(&source)?;
+    let module = wasm::compile(&code, &dialect)
+        .map_err(|e| RunError::Solver(format!("Could not compile image: {}", e)))?;
+    std::fs::write(path, module)
+        .map_err(|e| RunError::Input(format!("Could not write {}: {}", path, e)))
+}
@@ -0,0 +1,336 @@
+//! Coverage of runs of a `Program`: how many times each instruction was
+//! executed and how many times each address was read and written, reported
+//! as an annotated listing or an LCOV tracefile.
+use super::{Instr, ParameterMode, Pc, Program, Word};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Coverage {
+    /// The number of times the instruction at each address was executed
+    pub executed: BTreeMap<Pc, u64>,
+    pub reads: BTreeMap<Word, u64>,
+    pub writes: BTreeMap<Word, u64>,
+}
+
+/// A line of a listing: an instruction, or a word that is taken as data
+pub(crate) struct Line {
+    pub pc: Pc,
+    pub instr: Option<Instr>,
+}
+impl Line {
+    pub fn size(&self) -> usize {
+        self.instr.as_ref().map_or(1, Instr::size)
+    }
+}
+
+/// Splits `code` into instructions and data. The `anchors` are always
+/// instructions, elsewhere a word is an instruction if it decodes without
+/// running into an anchor.
+pub(crate) fn lines(code: &[Word], anchors: &BTreeSet<Pc>) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let next_anchor = anchors.range(pc + 1..).next().map_or(code.len(), |&pc| pc);
+
+        let instr = Instr::decode(&code[pc..])
+            .ok()
+            .filter(|instr| anchors.contains(&pc) || pc + instr.size() <= next_anchor);
+
+        let line = Line { pc, instr };
+        pc += line.size();
+        lines.push(line);
+    }
+
+    lines
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    /// Records that `program` is about to execute `instr`. Meant to be called
+    /// from the observer of `Program::run_with`.
+    pub fn record(&mut self, program: &Program, instr: &Instr) {
+        *self.executed.entry(program.pc).or_insert(0) += 1;
+
+        let Instr(opcode, params) = instr;
+        let operation = match program.dialect.operation(opcode.number) {
+            Some(operation) => operation,
+            None => return,
+        };
+
+        for (idx, (&mode, &param)) in opcode.modes.iter().zip(params).enumerate() {
+            let writes = operation.writes_to(idx);
+            if mode == ParameterMode::Immediate && !writes {
+                continue;
+            }
+
+            if let Ok(addr) = program.address(mode, param) {
+                let counts = if writes {
+                    &mut self.writes
+                } else {
+                    &mut self.reads
+                };
+                *counts.entry(addr).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Adds the counts of `other`, such as the coverage of a run with another
+    /// input.
+    pub fn merge(&mut self, other: &Coverage) {
+        for (counts, other) in [
+            (&mut self.reads, &other.reads),
+            (&mut self.writes, &other.writes),
+        ] {
+            for (&addr, &count) in other {
+                *counts.entry(addr).or_insert(0) += count;
+            }
+        }
+        for (&pc, &count) in &other.executed {
+            *self.executed.entry(pc).or_insert(0) += count;
+        }
+    }
+
+    /// The executed addresses are kept aligned as instructions
+    fn lines(&self, code: &[Word]) -> Vec<Line> {
+        lines(code, &self.executed.keys().copied().collect())
+    }
+
+    fn count(counts: &BTreeMap<Word, u64>, line: &Line) -> u64 {
+        counts
+            .range(line.pc as Word..(line.pc + line.size()) as Word)
+            .map(|(_, &count)| count)
+            .sum()
+    }
+
+    /// Lists `code`, which should be the memory at the end of the run so that
+    /// instructions the program wrote show as they were executed. Each line
+    /// has the number of times the instruction was executed, or `-` if it
+    /// never was, and the number of reads and writes of its words.
+    pub fn listing(&self, code: &[Word]) -> String {
+        let lines = self.lines(code);
+        let instrs = lines.iter().filter(|line| line.instr.is_some()).count();
+        let mut listing = String::new();
+
+        writeln!(
+            listing,
+            "# {} of {} instructions executed",
+            self.executed.len(),
+            instrs
+        )
+        .unwrap();
+
+        for line in &lines {
+            let hits = match (&line.instr, self.executed.get(&line.pc)) {
+                (_, Some(hits)) => hits.to_string(),
+                (Some(_), None) => "-".to_string(),
+                (None, None) => String::new(),
+            };
+            let text = match &line.instr {
+                Some(instr) => instr.to_string(),
+                None => format!("DATA {}", code[line.pc]),
+            };
+
+            let mut row = format!("{:>8} {:>6}  {:<28}", hits, line.pc, text);
+            for (tag, counts) in [("r", &self.reads), ("w", &self.writes)] {
+                match Coverage::count(counts, line) {
+                    0 => {}
+                    count => write!(row, " {}:{}", tag, count).unwrap(),
+                }
+            }
+            writeln!(listing, "{}", row.trim_end()).unwrap();
+        }
+
+        listing
+    }
+
+    /// An LCOV tracefile for `code` as the source file `name`, where every
+    /// instruction is a line numbered by its address plus one.
+    pub fn lcov(&self, name: &str, code: &[Word]) -> String {
+        let lines = self
+            .lines(code)
+            .into_iter()
+            .filter(|line| line.instr.is_some())
+            .collect::<Vec<_>>();
+        let mut lcov = format!("TN:\nSF:{}\n", name);
+        let mut hit = 0;
+
+        for line in &lines {
+            let hits = self.executed.get(&line.pc).copied().unwrap_or(0);
+            if hits > 0 {
+                hit += 1;
+            }
+            writeln!(lcov, "DA:{},{}", line.pc + 1, hits).unwrap();
+        }
+        writeln!(lcov, "LF:{}", lines.len()).unwrap();
+        writeln!(lcov, "LH:{}", hit).unwrap();
+        lcov.push_str("end_of_record\n");
+
+        lcov
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Limits;
+    use std::io::Cursor;
+
+    /// Outputs 1 if the input is 8 and 0 otherwise
+    const IS_EIGHT: &[Word] = &[
+        3, 20, 1008, 20, 8, 21, 1005, 21, 14, 104, 0, 1105, 1, 16, 104, 1, 99, 0, 0, 0, 0, 0,
+    ];
+
+    fn run(code: &[Word], input: &str) -> (Coverage, Program) {
+        let mut coverage = Coverage::new();
+        let mut program = Program::new(code.to_vec());
+
+        program
+            .run_with(
+                &mut Cursor::new(input),
+                &mut Cursor::new(Vec::new()),
+                &Limits::default(),
+                |program, instr| coverage.record(program, instr),
+            )
+            .unwrap();
+
+        (coverage, program)
+    }
+
+    #[test]
+    fn test_record() {
+        let (coverage, _) = run(IS_EIGHT, "8\n");
+
+        assert_eq!(
+            vec![0, 2, 6, 14, 16],
+            coverage.executed.keys().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(20, 1), (21, 1)],
+            coverage.reads.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(20, 1), (21, 1)],
+            coverage.writes.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_record_relative_addresses() {
+        // ARB #10; ADD [rb+1], #1, [rb+2]; FIN
+        let mut program = Program::with_dialect(
+            vec![109, 10, 21201, 1, 1, 2, 99, 0, 0, 0, 0, 0, 0],
+            crate::Dialect::DAY09,
+        );
+        let mut coverage = Coverage::new();
+
+        program
+            .run_with(
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+                &Limits::default(),
+                |program, instr| coverage.record(program, instr),
+            )
+            .unwrap();
+
+        assert_eq!(Some(&1), coverage.reads.get(&11));
+        assert_eq!(Some(&1), coverage.writes.get(&12));
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut coverage, _) = run(IS_EIGHT, "8\n");
+        let (other, _) = run(IS_EIGHT, "7\n");
+
+        coverage.merge(&other);
+
+        assert_eq!(
+            vec![(0, 2), (2, 2), (6, 2), (9, 1), (11, 1), (14, 1), (16, 2)],
+            coverage.executed.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&2), coverage.writes.get(&20));
+    }
+
+    #[test]
+    fn test_listing() {
+        let (coverage, program) = run(IS_EIGHT, "8\n");
+
+        assert_eq!(
+            "# 5 of 7 instructions executed
+       1      0  IN [20]
+       1      2  EQ [20], #8, [21]
+       1      6  JT [21], #14
+       -      9  OUT #0
+       -     11  JT #1, #16
+       1     14  OUT #1
+       1     16  FIN
+             17  DATA 0
+             18  DATA 0
+             19  DATA 0
+             20  DATA 8                       r:1 w:1
+             21  DATA 1                       r:1 w:1
+",
+            coverage.listing(&program.code)
+        );
+    }
+
+    #[test]
+    fn test_listing_keeps_executed_instructions_aligned() {
+        // Word 3 would decode as the start of an `Add` running over the
+        // executed `Fin`
+        let mut coverage = Coverage::new();
+        coverage.executed.insert(0, 1);
+        coverage.executed.insert(5, 1);
+
+        assert_eq!(
+            "# 2 of 2 instructions executed
+       1      0  JT #1, #5
+              3  DATA 1
+              4  DATA 0
+       1      5  FIN
+",
+            coverage.listing(&[1105, 1, 5, 1, 0, 99])
+        );
+    }
+
+    #[test]
+    fn test_lcov() {
+        let (coverage, program) = run(IS_EIGHT, "7\n");
+
+        assert_eq!(
+            "TN:
+SF:is_eight.txt
+DA:1,1
+DA:3,1
+DA:7,1
+DA:10,1
+DA:12,1
+DA:15,0
+DA:17,1
+LF:7
+LH:6
+end_of_record
+",
+            coverage.lcov("is_eight.txt", &program.code)
+        );
+    }
+
+    #[test]
+    fn test_lcov_counts_only_lines_hit() {
+        // JT #1, #2 jumps into its own parameters, to MUL [7], [7] -> [7];
+        // FIN. The MUL at 2 is executed but is not a line of the listing.
+        let (coverage, program) = run(&[1105, 1, 2, 7, 7, 7, 99, 0], "");
+
+        let lcov = coverage.lcov("jump.txt", &program.code);
+        assert!(
+            lcov.ends_with("DA:1,1\nDA:7,1\nLF:2\nLH:2\nend_of_record\n"),
+            "{}",
+            lcov
+        );
+    }
+}
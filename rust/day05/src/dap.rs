@@ -0,0 +1,850 @@
+//! A Debug Adapter Protocol server, so that editors can debug Intcode
+//! programs. https://microsoft.github.io/debug-adapter-protocol/
+//!
+//! The program is shown as a source with a line per address, the line of an
+//! address being the address plus one, and as a disassembly. The registers
+//! and memory are variables, and memory can also be read as bytes, with each
+//! word as 8 little endian bytes.
+//!
+//! The `launch` request takes the image as `program`, the values to input as
+//! `input`, the dialect as `dialect` (`day02`, `day05` or `day09`),
+//! `stopOnEntry` and `maxSteps`. Output is sent as output events. `continue`
+//! runs until a breakpoint is hit or the program finishes without handling
+//! any requests in the meantime, so there is no `pause`. Instead it stops by
+//! itself after `maxSteps` steps, so that a program that never halts can
+//! still be inspected. As there are no calls, there is no `stepOut`.
+use super::coverage;
+use super::loader::{self, Source};
+use super::{Dialect, Instr, Pc, Program, Word};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Cursor, Write};
+
+const THREAD: i64 = 1;
+const SOURCE_REFERENCE: i64 = 1;
+const REGISTERS: i64 = 1;
+const MEMORY: i64 = 2;
+const WORD_BYTES: usize = 8;
+/// The most steps `continue` takes unless `launch` sets `maxSteps`
+const MAX_STEPS: u64 = 10_000_000;
+/// The largest message accepted, far more than any request needs
+const MAX_MESSAGE_BYTES: usize = 1 << 24;
+
+type RequestResult = Result<Value, String>;
+
+/// Serves requests from `r` until it ends or the client disconnects.
+pub fn serve<R, W>(mut r: R, w: W) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut server = Server::new(w);
+
+    while let Some(request) = read_message(&mut r)? {
+        if !server.handle(&request)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_message<R: BufRead>(r: &mut R) -> io::Result<Option<Value>> {
+    let mut len = None;
+
+    loop {
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            len = value.trim().parse().ok();
+        }
+    }
+
+    let len = len.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No Content-Length"))?;
+    if len > MAX_MESSAGE_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Content-Length {} is larger than {}",
+                len, MAX_MESSAGE_BYTES
+            ),
+        ));
+    }
+    let mut body = vec![0; len];
+    r.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message<W: Write>(w: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(w, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    w.flush()
+}
+
+struct Server<W> {
+    out: W,
+    seq: i64,
+    name: String,
+    program: Option<Program>,
+    input: Cursor<Vec<u8>>,
+    stop_on_entry: bool,
+    max_steps: u64,
+    /// Set through `setBreakpoints`
+    source_breakpoints: BTreeSet<Pc>,
+    /// Set through `setInstructionBreakpoints`
+    instruction_breakpoints: BTreeSet<Pc>,
+}
+
+impl<W: Write> Server<W> {
+    fn new(out: W) -> Self {
+        Server {
+            out,
+            seq: 0,
+            name: String::new(),
+            program: None,
+            input: Cursor::new(Vec::new()),
+            stop_on_entry: false,
+            max_steps: MAX_STEPS,
+            source_breakpoints: BTreeSet::new(),
+            instruction_breakpoints: BTreeSet::new(),
+        }
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.out, &message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn stopped(&mut self, reason: &str) -> io::Result<()> {
+        self.event("stopped", json!({ "reason": reason, "threadId": THREAD }))
+    }
+
+    fn output(&mut self, category: &str, output: String) -> io::Result<()> {
+        self.event("output", json!({ "category": category, "output": output }))
+    }
+
+    /// Handles `request`, returning whether to keep serving
+    fn handle(&mut self, request: &Value) -> io::Result<bool> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let args = &request["arguments"];
+
+        let body = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsDisassembleRequest": true,
+                "supportsReadMemoryRequest": true,
+                "supportsInstructionBreakpoints": true,
+                "supportsSteppingGranularity": true,
+            })),
+            "launch" => self.launch(args),
+            "setBreakpoints" => Ok(self.set_breakpoints(args)),
+            "setInstructionBreakpoints" => Ok(self.set_instruction_breakpoints(args)),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => self.scopes(),
+            "variables" => self.variables(args),
+            "source" => self.source(),
+            "disassemble" => self.disassemble(args),
+            "readMemory" => self.read_memory(args),
+            "configurationDone" | "continue" | "next" | "stepIn" | "disconnect" => Ok(json!({})),
+            command => Err(format!("Unsupported command {}", command)),
+        };
+
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+            "success": body.is_ok(),
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)?;
+
+        match command {
+            "initialize" => self.event("initialized", json!({}))?,
+            "configurationDone" if self.stop_on_entry => self.stopped("entry")?,
+            "configurationDone" | "continue" => self.resume(false)?,
+            "next" | "stepIn" => self.resume(true)?,
+            "disconnect" => return Ok(false),
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn program(&self) -> Result<&Program, String> {
+        self.program
+            .as_ref()
+            .ok_or_else(|| "No program has been launched".to_string())
+    }
+
+    fn launch(&mut self, args: &Value) -> RequestResult {
+        let name = args["program"]
+            .as_str()
+            .ok_or("The program to launch is missing")?;
+        // Stdin carries the messages of the debugger, not an image
+        let source = match Source::from(name) {
+            Source::Stdin => return Err("The program can't be read from stdin".to_string()),
+            source => source,
+        };
+        let code = loader::load(&source).map_err(|e| e.to_string())?;
+
+        let dialect = match args["dialect"].as_str() {
+            Some("day02") => Dialect::DAY02,
+            Some("day05") | None => Dialect::DAY05,
+            Some("day09") => Dialect::DAY09,
+            Some(dialect) => return Err(format!("Unknown dialect {}", dialect)),
+        };
+
+        let input = args["input"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|value| value.as_i64().map(|value| format!("{}\n", value)))
+            .collect::<Option<String>>()
+            .ok_or("The input must be numbers")?;
+
+        self.name = name.to_string();
+        self.program = Some(Program::with_dialect(code, dialect));
+        self.input = Cursor::new(input.into_bytes());
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        self.max_steps = args["maxSteps"].as_u64().unwrap_or(MAX_STEPS);
+
+        Ok(json!({}))
+    }
+
+    fn verified(&self, pc: Pc) -> bool {
+        self.program
+            .as_ref()
+            .is_none_or(|program| pc < program.code.len())
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        let lines = args["breakpoints"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .filter_map(|line| Pc::try_from(line).ok())
+            .filter(|&line| line > 0)
+            .collect::<Vec<_>>();
+
+        self.source_breakpoints = lines.iter().map(|line| line - 1).collect();
+
+        let breakpoints = lines
+            .iter()
+            .map(|&line| json!({ "verified": self.verified(line - 1), "line": line }))
+            .collect::<Vec<_>>();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn set_instruction_breakpoints(&mut self, args: &Value) -> Value {
+        let breakpoints = args["breakpoints"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|breakpoint| {
+                let reference = breakpoint["instructionReference"].as_str().unwrap_or("");
+                let offset = breakpoint["offset"].as_i64().unwrap_or(0);
+
+                reference
+                    .parse::<Word>()
+                    .ok()
+                    .and_then(|pc| pc.checked_add(offset))
+                    .and_then(|pc| Pc::try_from(pc).ok())
+            })
+            .collect::<Vec<_>>();
+
+        self.instruction_breakpoints = breakpoints.iter().flatten().copied().collect();
+
+        let breakpoints = breakpoints
+            .iter()
+            .map(|pc| json!({ "verified": pc.is_some_and(|pc| self.verified(pc)) }))
+            .collect::<Vec<_>>();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Steps the program once, or until a breakpoint is reached if `step`
+    /// is false, reporting where it stopped.
+    fn resume(&mut self, step: bool) -> io::Result<()> {
+        for steps in 1.. {
+            let program = match &mut self.program {
+                Some(program) if !program.finished => program,
+                _ => return self.event("terminated", json!({})),
+            };
+
+            let mut out = vec![];
+            let result = program.step(&mut self.input, &mut out);
+            let (finished, pc) = (program.finished, program.pc);
+
+            if !out.is_empty() {
+                let output = format!("{}\n", String::from_utf8_lossy(&out));
+                self.output("stdout", output)?;
+            }
+
+            if let Err(e) = result {
+                self.output("stderr", format!("{:?}\n", e))?;
+                return self.event(
+                    "stopped",
+                    json!({
+                        "reason": "exception",
+                        "description": format!("{:?}", e),
+                        "threadId": THREAD,
+                    }),
+                );
+            }
+
+            if finished {
+                self.event("exited", json!({ "exitCode": 0 }))?;
+                return self.event("terminated", json!({}));
+            }
+            if step {
+                return self.stopped("step");
+            }
+            if self.source_breakpoints.contains(&pc) || self.instruction_breakpoints.contains(&pc) {
+                return self.stopped("breakpoint");
+            }
+            if steps >= self.max_steps {
+                return self.event(
+                    "stopped",
+                    json!({
+                        "reason": "pause",
+                        "description": format!("Paused after {} steps", steps),
+                        "threadId": THREAD,
+                    }),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn source_json(&self) -> Value {
+        json!({ "name": self.name, "sourceReference": SOURCE_REFERENCE })
+    }
+
+    fn stack_trace(&self) -> RequestResult {
+        let program = self.program()?;
+        let name = program
+            .code
+            .get(program.pc..)
+            .and_then(|code| Instr::decode_with(code, &program.dialect.extensions).ok())
+            .map_or_else(|| "??".to_string(), |instr| instr.to_string());
+
+        Ok(json!({
+            "stackFrames": [{
+                "id": 0,
+                "name": name,
+                "source": self.source_json(),
+                "line": program.pc + 1,
+                "column": 1,
+                "instructionPointerReference": program.pc.to_string(),
+            }],
+            "totalFrames": 1,
+        }))
+    }
+
+    fn scopes(&self) -> RequestResult {
+        let program = self.program()?;
+
+        Ok(json!({
+            "scopes": [
+                { "name": "Registers", "variablesReference": REGISTERS, "expensive": false },
+                {
+                    "name": "Memory",
+                    "variablesReference": MEMORY,
+                    "indexedVariables": program.code.len(),
+                    "expensive": true,
+                },
+            ]
+        }))
+    }
+
+    fn variables(&self, args: &Value) -> RequestResult {
+        let program = self.program()?;
+
+        let variables = match args["variablesReference"].as_i64() {
+            Some(REGISTERS) => vec![
+                json!({ "name": "pc", "value": program.pc.to_string(), "variablesReference": 0 }),
+                json!({
+                    "name": "rb",
+                    "value": program.relative_base.to_string(),
+                    "variablesReference": 0,
+                }),
+            ],
+            Some(MEMORY) => {
+                let start = args["start"].as_u64().unwrap_or(0) as usize;
+                let count = args["count"]
+                    .as_u64()
+                    .map_or(program.code.len(), |c| c as usize);
+
+                program
+                    .code
+                    .iter()
+                    .enumerate()
+                    .skip(start)
+                    .take(count)
+                    .map(|(addr, word)| {
+                        json!({
+                            "name": format!("[{}]", addr),
+                            "value": word.to_string(),
+                            "variablesReference": 0,
+                            "memoryReference": addr.to_string(),
+                        })
+                    })
+                    .collect()
+            }
+            _ => return Err("Unknown variables reference".to_string()),
+        };
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// The lines of the current memory, keeping `pc` and the breakpoints
+    /// aligned as instructions
+    fn lines(&self, program: &Program) -> Vec<coverage::Line> {
+        let mut anchors = &self.source_breakpoints | &self.instruction_breakpoints;
+        anchors.insert(program.pc);
+
+        coverage::lines(&program.code, &anchors)
+    }
+
+    /// A line per address: instructions where they start, the raw words of
+    /// their parameters, and data
+    fn source(&self) -> RequestResult {
+        let program = self.program()?;
+        let mut content = String::new();
+
+        for line in self.lines(program) {
+            match line.instr {
+                Some(instr) => {
+                    content.push_str(&format!("{}\n", instr));
+                    for param in &instr.1 {
+                        content.push_str(&format!("    {}\n", param));
+                    }
+                }
+                None => content.push_str(&format!("DATA {}\n", program.code[line.pc])),
+            }
+        }
+
+        Ok(json!({ "content": content }))
+    }
+
+    fn disassemble(&self, args: &Value) -> RequestResult {
+        let program = self.program()?;
+        let reference = args["memoryReference"]
+            .as_str()
+            .and_then(|reference| reference.parse::<i64>().ok())
+            .and_then(|reference| reference.checked_add(args["offset"].as_i64().unwrap_or(0)))
+            .ok_or("Invalid memory reference")?;
+        let offset = args["instructionOffset"].as_i64().unwrap_or(0);
+
+        // Beyond the lines there is nothing but invalid instructions to show
+        let lines = self.lines(program);
+        let count = args["instructionCount"]
+            .as_i64()
+            .unwrap_or(0)
+            .clamp(0, lines.len() as i64);
+        let first = (lines
+            .iter()
+            .position(|line| line.pc as i64 >= reference)
+            .unwrap_or(lines.len()) as i64)
+            .saturating_add(offset);
+
+        let instructions = (0..count)
+            .map(|idx| first.saturating_add(idx))
+            .map(
+                |idx| match usize::try_from(idx).ok().and_then(|idx| lines.get(idx)) {
+                    Some(line) => {
+                        let words = &program.code[line.pc..line.pc + line.size()];
+                        json!({
+                            "address": line.pc.to_string(),
+                            "instructionBytes": words
+                                .iter()
+                                .map(Word::to_string)
+                                .collect::<Vec<_>>()
+                                .join(","),
+                            "instruction": match &line.instr {
+                                Some(instr) => instr.to_string(),
+                                None => format!("DATA {}", words[0]),
+                            },
+                            "location": self.source_json(),
+                            "line": line.pc + 1,
+                        })
+                    }
+                    None => json!({
+                        "address": "?",
+                        "instruction": "??",
+                        "presentationHint": "invalid",
+                    }),
+                },
+            )
+            .collect::<Vec<_>>();
+
+        Ok(json!({ "instructions": instructions }))
+    }
+
+    fn read_memory(&self, args: &Value) -> RequestResult {
+        let program = self.program()?;
+        let start = args["memoryReference"]
+            .as_str()
+            .and_then(|reference| reference.parse::<i64>().ok())
+            .and_then(|addr| addr.checked_mul(WORD_BYTES as i64))
+            .and_then(|addr| addr.checked_add(args["offset"].as_i64().unwrap_or(0)))
+            .and_then(|addr| usize::try_from(addr).ok())
+            .ok_or("Invalid memory reference")?;
+        let count = args["count"].as_u64().unwrap_or(0) as usize;
+
+        let bytes = program
+            .code
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .skip(start)
+            .take(count)
+            .collect::<Vec<_>>();
+
+        Ok(json!({
+            "address": start.to_string(),
+            "data": base64(&bytes),
+            "unreadableBytes": count - bytes.len(),
+        }))
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (idx, &byte)| {
+            n | u32::from(byte) << (16 - 8 * idx)
+        });
+
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Outputs 1 if the input is 8 and 0 otherwise
+    const IS_EIGHT: &str = "3,20,1008,20,8,21,1005,21,14,104,0,1105,1,16,104,1,99,0,0,0,0,0";
+
+    /// Writes `code` to a file of its own and returns its path
+    fn image(test: &str, code: &str) -> String {
+        let path = std::env::temp_dir().join(format!("day05-dap-{}-{}", std::process::id(), test));
+        std::fs::write(&path, code).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn session(requests: &[Value]) -> Vec<Value> {
+        let mut input = vec![];
+        for (seq, request) in requests.iter().enumerate() {
+            let mut request = request.clone();
+            request["seq"] = json!(seq + 1);
+            request["type"] = json!("request");
+            write_message(&mut input, &request).unwrap();
+        }
+
+        let mut output = vec![];
+        serve(Cursor::new(input), &mut output).unwrap();
+
+        let mut r = Cursor::new(output);
+        let mut messages = vec![];
+        while let Some(message) = read_message(&mut r).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// The command of each response and the event of each event, with the
+    /// reason if it is a stop
+    fn kinds(messages: &[Value]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|message| match message["type"].as_str() {
+                Some("response") => message["command"].as_str().unwrap().to_string(),
+                _ => match message["body"]["reason"].as_str() {
+                    Some(reason) => format!("{}:{}", message["event"].as_str().unwrap(), reason),
+                    None => message["event"].as_str().unwrap().to_string(),
+                },
+            })
+            .collect()
+    }
+
+    fn response<'a>(messages: &'a [Value], command: &str) -> &'a Value {
+        messages
+            .iter()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_read_write_message() {
+        let mut bytes = vec![];
+        write_message(&mut bytes, &json!({ "a": 1 })).unwrap();
+
+        assert_eq!(b"Content-Length: 7\r\n\r\n{\"a\":1}".to_vec(), bytes);
+        assert_eq!(
+            Some(json!({ "a": 1 })),
+            read_message(&mut Cursor::new(bytes)).unwrap()
+        );
+        assert_eq!(None, read_message(&mut Cursor::new(vec![])).unwrap());
+
+        let huge = format!("Content-Length: {}\r\n\r\n", u64::MAX);
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            read_message(&mut Cursor::new(huge)).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn test_run_to_breakpoint() {
+        let program = image("breakpoint", IS_EIGHT);
+        let messages = session(&[
+            json!({ "command": "initialize", "arguments": {} }),
+            json!({ "command": "launch", "arguments": { "program": program, "input": [8] } }),
+            json!({
+                "command": "setBreakpoints",
+                "arguments": { "source": {}, "breakpoints": [{ "line": 15 }, { "line": 100 }] },
+            }),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "stackTrace", "arguments": { "threadId": THREAD } }),
+            json!({ "command": "continue", "arguments": { "threadId": THREAD } }),
+        ]);
+
+        assert_eq!(
+            vec![
+                "initialize",
+                "initialized",
+                "launch",
+                "setBreakpoints",
+                "configurationDone",
+                "output",
+                "stopped:breakpoint",
+                "stackTrace",
+                "continue",
+                "output",
+                "exited",
+                "terminated",
+            ],
+            kinds(&messages)
+        );
+        assert_eq!(
+            json!([{ "verified": true, "line": 15 }, { "verified": false, "line": 100 }]),
+            response(&messages, "setBreakpoints")["body"]["breakpoints"]
+        );
+
+        let frame = &response(&messages, "stackTrace")["body"]["stackFrames"][0];
+        assert_eq!(json!(15), frame["line"]);
+        assert_eq!(json!("OUT #1"), frame["name"]);
+
+        let outputs = messages
+            .iter()
+            .filter(|m| m["event"] == "output")
+            .map(|m| m["body"]["output"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Input: \n", "1\n"], outputs);
+    }
+
+    #[test]
+    fn test_step_and_inspect() {
+        let program = image("step", IS_EIGHT);
+        let messages = session(&[
+            json!({ "command": "launch", "arguments": {
+                "program": program,
+                "input": [7],
+                "stopOnEntry": true,
+            }}),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "next", "arguments": { "threadId": THREAD } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": REGISTERS } }),
+            json!({ "command": "variables", "arguments": {
+                "variablesReference": MEMORY,
+                "start": 20,
+                "count": 1,
+            }}),
+            json!({ "command": "disconnect" }),
+            json!({ "command": "threads" }),
+        ]);
+
+        assert_eq!(
+            vec![
+                "launch",
+                "configurationDone",
+                "stopped:entry",
+                "next",
+                "output",
+                "stopped:step",
+                "variables",
+                "variables",
+                "disconnect",
+            ],
+            kinds(&messages)
+        );
+
+        let registers = &messages[6]["body"]["variables"];
+        assert_eq!(json!("2"), registers[0]["value"]);
+        assert_eq!(json!("0"), registers[1]["value"]);
+
+        let memory = &messages[7]["body"]["variables"];
+        assert_eq!(json!("[20]"), memory[0]["name"]);
+        assert_eq!(json!("7"), memory[0]["value"]);
+    }
+
+    #[test]
+    fn test_instruction_breakpoints_and_disassembly() {
+        let program = image("disassemble", IS_EIGHT);
+        let messages = session(&[
+            json!({ "command": "launch", "arguments": { "program": program, "input": [7] } }),
+            json!({ "command": "setInstructionBreakpoints", "arguments": {
+                "breakpoints": [{ "instructionReference": "6", "offset": 3 }],
+            }}),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "disassemble", "arguments": {
+                "memoryReference": "9",
+                "instructionOffset": -1,
+                "instructionCount": 3,
+            }}),
+            json!({ "command": "readMemory", "arguments": {
+                "memoryReference": "16",
+                "count": 10,
+            }}),
+        ]);
+
+        assert!(kinds(&messages).contains(&"stopped:breakpoint".to_string()));
+
+        let instructions = &response(&messages, "disassemble")["body"]["instructions"];
+        assert_eq!(json!("6"), instructions[0]["address"]);
+        assert_eq!(json!("JT [21], #14"), instructions[0]["instruction"]);
+        assert_eq!(json!("OUT #0"), instructions[1]["instruction"]);
+        assert_eq!(json!("104,0"), instructions[1]["instructionBytes"]);
+        assert_eq!(json!("JT #1, #16"), instructions[2]["instruction"]);
+
+        let memory = &response(&messages, "readMemory")["body"];
+        // 99 and the first two bytes of 0
+        assert_eq!(json!("YwAAAAAAAAAAAA=="), memory["data"]);
+        assert_eq!(json!(0), memory["unreadableBytes"]);
+    }
+
+    #[test]
+    fn test_continue_pauses_after_max_steps() {
+        let program = image("pause", "1105,1,0");
+        let messages = session(&[
+            json!({ "command": "launch", "arguments": { "program": program, "maxSteps": 100 } }),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "continue", "arguments": { "threadId": THREAD } }),
+        ]);
+
+        assert_eq!(
+            vec![
+                "launch",
+                "configurationDone",
+                "stopped:pause",
+                "continue",
+                "stopped:pause",
+            ],
+            kinds(&messages)
+        );
+        assert_eq!(
+            json!("Paused after 100 steps"),
+            messages[2]["body"]["description"]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_out_of_range() {
+        let program = image("disassemble-range", "1101,1,2,0,99");
+        let messages = session(&[
+            json!({ "command": "launch", "arguments": { "program": program } }),
+            json!({ "command": "disassemble", "arguments": {
+                "memoryReference": "0",
+                "instructionOffset": i64::MAX,
+                "instructionCount": i64::MAX,
+            }}),
+            json!({ "command": "disassemble", "arguments": {
+                "memoryReference": i64::MAX.to_string(),
+                "offset": 1,
+                "instructionCount": 1,
+            }}),
+        ]);
+
+        let instructions = &messages[1]["body"]["instructions"];
+        assert_eq!(2, instructions.as_array().unwrap().len());
+        assert_eq!(json!("invalid"), instructions[0]["presentationHint"]);
+        assert_eq!(json!("Invalid memory reference"), messages[2]["message"]);
+    }
+
+    #[test]
+    fn test_source() {
+        let program = image("source", "1101,1,2,0,99,7");
+        let messages = session(&[
+            json!({ "command": "launch", "arguments": { "program": program } }),
+            json!({ "command": "source", "arguments": { "sourceReference": SOURCE_REFERENCE } }),
+        ]);
+
+        assert_eq!(
+            json!("ADD #1, #2, [0]\n    1\n    2\n    0\nFIN\nDATA 7\n"),
+            response(&messages, "source")["body"]["content"]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let messages = session(&[
+            json!({ "command": "stackTrace" }),
+            json!({ "command": "launch", "arguments": { "program": "/nonexistent" } }),
+            json!({ "command": "pause" }),
+            json!({ "command": "stepOut" }),
+            json!({ "command": "launch", "arguments": { "program": "-" } }),
+        ]);
+
+        assert!(messages.iter().all(|m| m["success"] == false));
+        assert_eq!(
+            json!("No program has been launched"),
+            messages[0]["message"]
+        );
+        assert_eq!(json!("Unsupported command pause"), messages[2]["message"]);
+        assert_eq!(json!("Unsupported command stepOut"), messages[3]["message"]);
+        assert_eq!(
+            json!("The program can't be read from stdin"),
+            messages[4]["message"]
+        );
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+    }
+}
@@ -0,0 +1,793 @@
+//! Decompiles Intcode images to C-like pseudo-code.
+//!
+//! The image is disassembled with a linear sweep. When a word doesn't decode,
+//! typically because the program patches it before it's executed, it is
+//! taken as data and decoding picks up again at the first address from which
+//! a few instructions decode in a row.
+//!
+//! Backward jumps are recovered as loops, forward conditional jumps as
+//! if/else, and jumps out of or back to the start of a loop as `break` and
+//! `continue`. Any other jump becomes a `goto`. Storing the address after a
+//! jump on the stack, relative to the relative base, and then jumping is
+//! taken as a call, and jumping to an address on the stack as a return.
+use super::opcodes::{self, ARB, FIN, IN, JF, JT, LT, OUT};
+use super::{Instr, ParameterMode, Pc, Word};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The number of instructions that must decode in a row after a word that
+/// didn't for decoding to pick up again
+const RESYNC_INSTRS: usize = 3;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Const(Word),
+    Mem(Word),
+    /// `mem[rb + offset]`
+    Rel(Word),
+    Input,
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinOp {
+    Add,
+    Mul,
+    Lt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Stmt {
+    Assign(Expr, Expr),
+    Output(Expr),
+    /// `rb += value`
+    AdjustBase(Expr),
+    Halt,
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    Loop(Vec<Stmt>),
+    DoWhile(Vec<Stmt>, Expr),
+    Break,
+    Continue,
+    Call(Pc),
+    Return,
+    Goto(Pc),
+    GotoComputed(Expr),
+    Label(Pc),
+    /// Words that don't decode as instructions
+    Data(Pc, Vec<Word>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub entry: Pc,
+    pub body: Vec<Stmt>,
+}
+
+/// Decompiles `code` into `main`, starting at address 0, and a function for
+/// every address that is called.
+pub fn decompile(code: &[Word]) -> Vec<Function> {
+    let mut decompiler = Decompiler::new(code);
+
+    let mut entries = decompiler
+        .calls
+        .values()
+        .map(|&(target, _)| target)
+        .filter(|&target| target < code.len())
+        .collect::<BTreeSet<_>>();
+    entries.insert(0);
+    let entries = entries.into_iter().collect::<Vec<_>>();
+
+    let regions = entries
+        .iter()
+        .enumerate()
+        .map(|(idx, &entry)| (entry, entries.get(idx + 1).copied().unwrap_or(code.len())))
+        .collect::<Vec<_>>();
+
+    // Structure everything once to find out which addresses need a label
+    for &(from, to) in &regions {
+        decompiler.block(from, to, None);
+    }
+    decompiler.labels = std::mem::take(&mut decompiler.gotos);
+
+    regions
+        .into_iter()
+        .map(|(from, to)| Function {
+            entry: from,
+            body: decompiler.block(from, to, None),
+        })
+        .collect()
+}
+
+enum Item {
+    Instr(Instr),
+    Data(Word),
+}
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Instr(instr) => instr.size(),
+            Item::Data(_) => 1,
+        }
+    }
+}
+
+/// Whether a jump is taken
+enum Taken {
+    Never,
+    Always,
+    When(Expr),
+}
+
+/// The loop being structured
+#[derive(Clone, Copy)]
+struct Scope {
+    header: Pc,
+    exit: Pc,
+}
+
+struct Decompiler {
+    items: BTreeMap<Pc, Item>,
+    /// Every immediate jump target
+    targets: BTreeSet<Pc>,
+    /// The calls by the address of the instruction storing the return
+    /// address, as the called address and the return address
+    calls: BTreeMap<Pc, (Pc, Pc)>,
+    labels: BTreeSet<Pc>,
+    gotos: BTreeSet<Pc>,
+}
+impl Decompiler {
+    fn new(code: &[Word]) -> Self {
+        let items = disassemble(code);
+
+        let targets = items
+            .values()
+            .filter_map(|item| match item {
+                Item::Instr(Instr(opcode, params))
+                    if (opcode.number == JT || opcode.number == JF)
+                        && opcode.modes[1] == ParameterMode::Immediate =>
+                {
+                    Pc::try_from(params[1]).ok()
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut decompiler = Decompiler {
+            items,
+            targets,
+            calls: BTreeMap::new(),
+            labels: BTreeSet::new(),
+            gotos: BTreeSet::new(),
+        };
+        decompiler.calls = decompiler
+            .items
+            .keys()
+            .filter_map(|&pc| decompiler.call_at(pc).map(|call| (pc, call)))
+            .collect();
+        decompiler
+    }
+
+    fn instr(&self, pc: Pc) -> Option<&Instr> {
+        match self.items.get(&pc) {
+            Some(Item::Instr(instr)) => Some(instr),
+            _ => None,
+        }
+    }
+
+    /// Matches a call at `pc`: an `Add` or `Mul` storing the return address
+    /// on the stack, followed by a jump that is always taken
+    fn call_at(&self, pc: Pc) -> Option<(Pc, Pc)> {
+        let Instr(opcode, params) = self.instr(pc)?;
+        if ![opcodes::ADD, opcodes::MUL].contains(&opcode.number)
+            || opcode.modes[2] != ParameterMode::Relative
+        {
+            return None;
+        }
+
+        let jump_pc = pc + 1 + params.len();
+        let jump = self.instr(jump_pc)?;
+        let ret = jump_pc + jump.size();
+
+        match (self.assignment(pc)?, self.jump(jump_pc)?) {
+            ((_, Expr::Const(addr)), (Taken::Always, Expr::Const(target)))
+                if Pc::try_from(addr) == Ok(ret) =>
+            {
+                Some((Pc::try_from(target).ok()?, ret))
+            }
+            _ => None,
+        }
+    }
+
+    /// The place and value of an instruction that writes to memory
+    fn assignment(&self, pc: Pc) -> Option<(Expr, Expr)> {
+        let Instr(opcode, params) = self.instr(pc)?;
+        let arg = |idx: usize| operand(opcode.modes[idx], params[idx]);
+
+        let op = match opcode.number {
+            IN => return Some((place(opcode.modes[0], params[0]), Expr::Input)),
+            opcodes::ADD => BinOp::Add,
+            opcodes::MUL => BinOp::Mul,
+            LT => BinOp::Lt,
+            opcodes::EQ => BinOp::Eq,
+            _ => return None,
+        };
+        Some((
+            place(opcode.modes[2], params[2]),
+            Expr::binary(arg(0), op, arg(1)),
+        ))
+    }
+
+    /// When the jump at `pc` is taken and its target
+    fn jump(&self, pc: Pc) -> Option<(Taken, Expr)> {
+        let Instr(opcode, params) = self
+            .instr(pc)
+            .filter(|Instr(opcode, _)| opcode.number == JT || opcode.number == JF)?;
+        let target = operand(opcode.modes[1], params[1]);
+
+        let taken = match (opcode.number, operand(opcode.modes[0], params[0])) {
+            (JT, Expr::Const(0)) => Taken::Never,
+            (JF, Expr::Const(n)) if n != 0 => Taken::Never,
+            (JT, Expr::Const(_)) | (JF, Expr::Const(_)) => Taken::Always,
+            (JT, value) => Taken::When(self.condition(pc, value)),
+            (JF, value) => Taken::When(self.condition(pc, value).negate()),
+            _ => return None,
+        };
+        Some((taken, target))
+    }
+
+    /// Folds a comparison into the condition of the jump at `pc` that tests
+    /// its result, as long as nothing can jump in between them
+    fn condition(&self, pc: Pc, value: Expr) -> Expr {
+        if self.targets.contains(&pc) {
+            return value;
+        }
+
+        let previous = self.items.range(..pc).next_back();
+        match previous {
+            Some((&prev, item)) if prev + item.size() == pc => match self.assignment(prev) {
+                Some((place, cmp @ Expr::Binary(_, BinOp::Lt, _)))
+                | Some((place, cmp @ Expr::Binary(_, BinOp::Eq, _)))
+                    if place == value && !cmp.contains(&value) =>
+                {
+                    cmp
+                }
+                _ => value,
+            },
+            _ => value,
+        }
+    }
+
+    /// The last jump in `[pc, to)` back to `pc`, if any
+    fn back_edge(&self, pc: Pc, to: Pc) -> Option<Pc> {
+        self.items
+            .range(pc..to)
+            .rev()
+            .find(|&(&addr, item)| {
+                addr + item.size() <= to
+                    && !self
+                        .calls
+                        .values()
+                        .any(|&(_, ret)| ret == addr + item.size())
+                    && match self.jump(addr) {
+                        Some((Taken::Never, _)) | None => false,
+                        Some((_, target)) => target == Expr::Const(pc as Word),
+                    }
+            })
+            .map(|(&addr, _)| addr)
+    }
+
+    fn jump_to(&mut self, target: Expr, scope: Option<Scope>) -> Stmt {
+        match target {
+            Expr::Const(t) => match (Pc::try_from(t), scope) {
+                (Ok(t), Some(scope)) if t == scope.exit => Stmt::Break,
+                (Ok(t), Some(scope)) if t == scope.header => Stmt::Continue,
+                (Ok(t), _) => {
+                    self.gotos.insert(t);
+                    Stmt::Goto(t)
+                }
+                (Err(_), _) => Stmt::GotoComputed(Expr::Const(t)),
+            },
+            Expr::Rel(_) => Stmt::Return,
+            target => Stmt::GotoComputed(target),
+        }
+    }
+
+    /// Structures the code in `[from, to)`
+    fn block(&mut self, from: Pc, to: Pc, scope: Option<Scope>) -> Vec<Stmt> {
+        let mut stmts = vec![];
+        let mut pc = from;
+
+        while pc < to {
+            if let Some(latch) = self.back_edge(pc, to) {
+                let (taken, _) = self.jump(latch).expect("A back edge is a jump");
+                let exit = latch + self.items[&latch].size();
+                let body = self.block(pc, latch, Some(Scope { header: pc, exit }));
+
+                stmts.push(match taken {
+                    Taken::When(cond) => Stmt::DoWhile(body, cond),
+                    _ => Stmt::Loop(body),
+                });
+                pc = exit;
+                continue;
+            }
+
+            if self.labels.contains(&pc) {
+                stmts.push(Stmt::Label(pc));
+            }
+
+            let size = match self.items.get(&pc) {
+                Some(item) => item.size(),
+                None => break,
+            };
+            let next = pc + size;
+
+            if let Some(&(target, ret)) = self.calls.get(&pc).filter(|&&(_, ret)| ret <= to) {
+                stmts.push(Stmt::Call(target));
+                pc = ret;
+                continue;
+            }
+
+            if let Some((taken, target)) = self.jump(pc) {
+                match taken {
+                    Taken::Never => {}
+                    Taken::Always => stmts.push(self.jump_to(target, scope)),
+                    Taken::When(cond) => match target {
+                        Expr::Const(t)
+                            if t as Pc > next
+                                && t as Pc <= to
+                                && scope.is_none_or(|s| s.exit != t as Pc) =>
+                        {
+                            let t = t as Pc;
+                            let (then, otherwise, end) = match self.else_jump(next, t, to) {
+                                Some((at, end)) => {
+                                    (self.block(next, at, scope), self.block(t, end, scope), end)
+                                }
+                                None => (self.block(next, t, scope), vec![], t),
+                            };
+
+                            stmts.push(Stmt::If(cond.negate(), then, otherwise));
+                            pc = end;
+                            continue;
+                        }
+                        Expr::Const(t) if t as Pc == next => {}
+                        target => {
+                            let jump = self.jump_to(target, scope);
+                            stmts.push(Stmt::If(cond, vec![jump], vec![]))
+                        }
+                    },
+                }
+                pc = next;
+                continue;
+            }
+
+            match &self.items[&pc] {
+                Item::Data(_) => {
+                    let words = self
+                        .items
+                        .range(pc..to)
+                        .map_while(|(_, item)| match item {
+                            Item::Data(word) => Some(*word),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    let len = words.len();
+
+                    stmts.push(Stmt::Data(pc, words));
+                    pc += len;
+                    continue;
+                }
+                Item::Instr(Instr(opcode, params)) => {
+                    let arg = |idx: usize| operand(opcode.modes[idx], params[idx]);
+
+                    stmts.push(match opcode.number {
+                        OUT => Stmt::Output(arg(0)),
+                        ARB => Stmt::AdjustBase(arg(0)),
+                        FIN => Stmt::Halt,
+                        _ => {
+                            let (place, value) =
+                                self.assignment(pc).expect("Every other operation writes");
+                            Stmt::Assign(place, value)
+                        }
+                    });
+                }
+            }
+            pc = next;
+        }
+
+        stmts
+    }
+
+    /// The jump at the end of the `then` branch `[from, to)` over an `else`
+    /// branch ending within `end`, as its address and target
+    fn else_jump(&self, from: Pc, to: Pc, end: Pc) -> Option<(Pc, Pc)> {
+        let (&at, item) = self.items.range(from..to).next_back()?;
+        if at + item.size() != to || self.calls.values().any(|&(_, ret)| ret == to) {
+            return None;
+        }
+
+        match self.jump(at)? {
+            (Taken::Always, Expr::Const(target)) if target as Pc > to && target as Pc <= end => {
+                Some((at, target as Pc))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn disassemble(code: &[Word]) -> BTreeMap<Pc, Item> {
+    let mut items = BTreeMap::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        match Instr::decode(&code[pc..]) {
+            Ok(instr) => {
+                let size = instr.size();
+                items.insert(pc, Item::Instr(instr));
+                pc += size;
+            }
+            Err(_) => {
+                let resync = (pc + 1..=pc + 4)
+                    .find(|&at| decodes(code, at))
+                    .unwrap_or(pc + 1)
+                    .min(code.len());
+
+                for (at, &word) in code.iter().enumerate().take(resync).skip(pc) {
+                    items.insert(at, Item::Data(word));
+                }
+                pc = resync;
+            }
+        }
+    }
+
+    items
+}
+
+/// Whether `RESYNC_INSTRS` instructions decode from `pc`, or up to the end of
+/// the code
+fn decodes(code: &[Word], mut pc: Pc) -> bool {
+    for _ in 0..RESYNC_INSTRS {
+        match code.get(pc..).filter(|rest| !rest.is_empty()) {
+            None => return true,
+            Some(rest) => match Instr::decode(rest) {
+                Ok(instr) => pc += instr.size(),
+                Err(_) => return false,
+            },
+        }
+    }
+    true
+}
+
+/// The value of a parameter that is read
+fn operand(mode: ParameterMode, param: Word) -> Expr {
+    match mode {
+        ParameterMode::Position => Expr::Mem(param),
+        ParameterMode::Immediate => Expr::Const(param),
+        ParameterMode::Relative => Expr::Rel(param),
+    }
+}
+
+/// The memory a parameter that is written refers to
+fn place(mode: ParameterMode, param: Word) -> Expr {
+    match mode {
+        ParameterMode::Relative => Expr::Rel(param),
+        _ => Expr::Mem(param),
+    }
+}
+
+impl Expr {
+    /// Builds `a op b`, folding constants and dropping identities
+    fn binary(a: Expr, op: BinOp, b: Expr) -> Expr {
+        match (a, op, b) {
+            (Expr::Const(a), BinOp::Add, Expr::Const(b)) if a.checked_add(b).is_some() => {
+                Expr::Const(a + b)
+            }
+            (Expr::Const(a), BinOp::Mul, Expr::Const(b)) if a.checked_mul(b).is_some() => {
+                Expr::Const(a * b)
+            }
+            (Expr::Const(a), BinOp::Lt, Expr::Const(b)) => Expr::Const((a < b) as Word),
+            (Expr::Const(a), BinOp::Eq, Expr::Const(b)) => Expr::Const((a == b) as Word),
+            (Expr::Const(0), BinOp::Add, e)
+            | (e, BinOp::Add, Expr::Const(0))
+            | (Expr::Const(1), BinOp::Mul, e)
+            | (e, BinOp::Mul, Expr::Const(1)) => e,
+            (a, op, b) => Expr::Binary(Box::new(a), op, Box::new(b)),
+        }
+    }
+
+    fn negate(self) -> Expr {
+        match self {
+            Expr::Const(n) => Expr::Const((n == 0) as Word),
+            Expr::Not(e) => *e,
+            Expr::Binary(a, op, b) => match op {
+                BinOp::Lt => Expr::Binary(a, BinOp::Ge, b),
+                BinOp::Ge => Expr::Binary(a, BinOp::Lt, b),
+                BinOp::Eq => Expr::Binary(a, BinOp::Ne, b),
+                BinOp::Ne => Expr::Binary(a, BinOp::Eq, b),
+                _ => Expr::Not(Box::new(Expr::Binary(a, op, b))),
+            },
+            e => Expr::Not(Box::new(e)),
+        }
+    }
+
+    fn contains(&self, other: &Expr) -> bool {
+        self == other
+            || match self {
+                Expr::Binary(a, _, b) => a.contains(other) || b.contains(other),
+                Expr::Not(e) => e.contains(other),
+                _ => false,
+            }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operand = |f: &mut fmt::Formatter, e: &Expr| match e {
+            Expr::Binary(..) => write!(f, "({})", e),
+            e => write!(f, "{}", e),
+        };
+
+        match self {
+            Expr::Const(n) => write!(f, "{}", n),
+            Expr::Mem(addr) => write!(f, "mem[{}]", addr),
+            Expr::Rel(0) => write!(f, "mem[rb]"),
+            Expr::Rel(offset) if *offset < 0 => write!(f, "mem[rb - {}]", -(*offset as i128)),
+            Expr::Rel(offset) => write!(f, "mem[rb + {}]", offset),
+            Expr::Input => write!(f, "input()"),
+            Expr::Binary(a, BinOp::Add, b) => match **b {
+                Expr::Const(n) if n < 0 => {
+                    operand(f, a)?;
+                    write!(f, " - {}", -(n as i128))
+                }
+                _ => {
+                    operand(f, a)?;
+                    write!(f, " + ")?;
+                    operand(f, b)
+                }
+            },
+            Expr::Binary(a, op, b) => {
+                operand(f, a)?;
+                write!(f, " {} ", op)?;
+                operand(f, b)
+            }
+            Expr::Not(e) => {
+                write!(f, "!")?;
+                operand(f, e)
+            }
+        }
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            BinOp::Add => "+",
+            BinOp::Mul => "*",
+            BinOp::Lt => "<",
+            BinOp::Ge => ">=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+        })
+    }
+}
+
+fn name(entry: Pc) -> String {
+    match entry {
+        0 => "main".to_string(),
+        entry => format!("sub_{}", entry),
+    }
+}
+
+fn fmt_block(f: &mut fmt::Formatter, stmts: &[Stmt], depth: usize) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+
+    for stmt in stmts {
+        match stmt {
+            Stmt::Assign(place, value) => writeln!(f, "{}{} = {};", indent, place, value)?,
+            Stmt::Output(value) => writeln!(f, "{}output({});", indent, value)?,
+            Stmt::AdjustBase(value) => writeln!(f, "{}rb += {};", indent, value)?,
+            Stmt::Halt => writeln!(f, "{}halt();", indent)?,
+            Stmt::If(cond, then, otherwise) => {
+                writeln!(f, "{}if ({}) {{", indent, cond)?;
+                fmt_block(f, then, depth + 1)?;
+                if !otherwise.is_empty() {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    fmt_block(f, otherwise, depth + 1)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+            Stmt::Loop(body) => {
+                writeln!(f, "{}while (1) {{", indent)?;
+                fmt_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Stmt::DoWhile(body, cond) => {
+                writeln!(f, "{}do {{", indent)?;
+                fmt_block(f, body, depth + 1)?;
+                writeln!(f, "{}}} while ({});", indent, cond)?;
+            }
+            Stmt::Break => writeln!(f, "{}break;", indent)?,
+            Stmt::Continue => writeln!(f, "{}continue;", indent)?,
+            Stmt::Call(target) => writeln!(f, "{}{}();", indent, name(*target))?,
+            Stmt::Return => writeln!(f, "{}return;", indent)?,
+            Stmt::Goto(target) => writeln!(f, "{}goto L{};", indent, target)?,
+            Stmt::GotoComputed(target) => writeln!(f, "{}goto *{};", indent, target)?,
+            Stmt::Label(pc) => writeln!(f, "{}L{}:", "    ".repeat(depth - 1), pc)?,
+            Stmt::Data(pc, words) => {
+                let words = words.iter().map(Word::to_string).collect::<Vec<_>>();
+                writeln!(f, "{}/* {}: {} */", indent, pc, words.join(", "))?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "void {}() {{", name(self.entry))?;
+        fmt_block(f, &self.body, 1)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{loader, Dialect, Limits, Program};
+    use std::io::Cursor;
+
+    fn decompiled(code: &[Word]) -> String {
+        decompile(code)
+            .iter()
+            .map(|function| format!("{}\n", function))
+            .collect()
+    }
+
+    #[test]
+    fn test_straight_line() {
+        assert_eq!(
+            "void main() {
+    mem[5] = input();
+    output(mem[5]);
+    halt();
+    /* 5: 0 */
+}
+",
+            decompiled(&[3, 5, 4, 5, 99, 0])
+        );
+    }
+
+    #[test]
+    fn test_if_else() {
+        // IN [20]; EQ [20], #8 -> [21]; JT [21], #14; OUT #0; JT #1, #16;
+        // OUT #1; FIN
+        let code = [
+            3, 20, 1008, 20, 8, 21, 1005, 21, 14, 104, 0, 1105, 1, 16, 104, 1, 99,
+        ];
+
+        assert_eq!(
+            "void main() {
+    mem[20] = input();
+    mem[21] = mem[20] == 8;
+    if (mem[20] != 8) {
+        output(0);
+    } else {
+        output(1);
+    }
+    halt();
+}
+",
+            decompiled(&code)
+        );
+    }
+
+    #[test]
+    fn test_do_while() {
+        // IN [20]; OUT [20]; ADD [20], #-1 -> [20]; JT [20], #2; FIN
+        let code = [3, 20, 4, 20, 1001, 20, -1, 20, 1005, 20, 2, 99];
+
+        assert_eq!(
+            "void main() {
+    mem[20] = input();
+    do {
+        output(mem[20]);
+        mem[20] = mem[20] - 1;
+    } while (mem[20]);
+    halt();
+}
+",
+            decompiled(&code)
+        );
+    }
+
+    #[test]
+    fn test_loop_with_break() {
+        // IN [20]; JF [20], #14; OUT #1; ADD [20], #-1 -> [20]; JT #1, #2; FIN
+        let code = [
+            3, 20, 1006, 20, 14, 104, 1, 1001, 20, -1, 20, 1105, 1, 2, 99,
+        ];
+
+        assert_eq!(
+            "void main() {
+    mem[20] = input();
+    while (1) {
+        if (!mem[20]) {
+            break;
+        }
+        output(1);
+        mem[20] = mem[20] - 1;
+    }
+    halt();
+}
+",
+            decompiled(&code)
+        );
+    }
+
+    #[test]
+    fn test_goto() {
+        // JT #1, #5; FIN; FIN; OUT #1; FIN
+        let code = [1105, 1, 5, 99, 99, 104, 1, 99];
+
+        assert_eq!(
+            "void main() {
+    goto L5;
+    halt();
+    halt();
+L5:
+    output(1);
+    halt();
+}
+",
+            decompiled(&code)
+        );
+    }
+
+    #[test]
+    fn test_call_and_return() {
+        // ARB #30; ADD #9, #0 -> [rb]; JT #1, #10; FIN; OUT #7; JT #1, [rb]
+        let code = [109, 30, 21101, 9, 0, 0, 1105, 1, 10, 99, 104, 7, 2105, 1, 0];
+
+        assert_eq!(
+            "void main() {
+    rb += 30;
+    sub_10();
+    halt();
+}
+void sub_10() {
+    output(7);
+    return;
+}
+",
+            decompiled(&code)
+        );
+
+        let mut program = Program::with_dialect(code.to_vec(), Dialect::DAY09);
+        let mut out = Cursor::new(Vec::new());
+        program
+            .run(&mut Cursor::new(""), &mut out, &Limits::default())
+            .unwrap();
+        assert_eq!(b"7".to_vec(), out.into_inner());
+    }
+
+    #[test]
+    fn test_resyncs_after_patched_words() {
+        let code = loader::parse_text(include_str!("../input.txt")).unwrap();
+        let decompiled = decompiled(&code);
+
+        assert!(decompiled.starts_with(
+            "void main() {
+    mem[225] = input();
+    mem[6] = mem[225] + mem[6];
+    /* 6: 1100, 1, 238, 225 */
+    output(0);
+"
+        ));
+    }
+}
@@ -0,0 +1,373 @@
+//! Devices mapped into the memory of a `Program`.
+//!
+//! A device attached at an address takes the reads and writes of the range of
+//! addresses starting there, instead of the memory. Only parameters go through
+//! the devices, instructions are always fetched from the memory.
+use super::{ProgramError, ProgramResult, Word};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+pub trait Device: Send {
+    /// The number of addresses the device takes
+    fn size(&self) -> usize;
+    /// Reads the word at `offset` from the address the device is attached at
+    fn read(&mut self, offset: usize) -> Word;
+    /// Writes the word at `offset`, or returns false if it can't be written
+    fn write(&mut self, offset: usize, value: Word) -> bool;
+}
+
+/// A device attached to a program, which the host can keep using
+pub type Shared<D> = Arc<Mutex<D>>;
+
+struct Mapping {
+    start: Word,
+    end: Word,
+    device: Arc<Mutex<dyn Device>>,
+}
+
+#[derive(Default)]
+pub struct Devices {
+    mappings: Vec<Mapping>,
+}
+
+impl Devices {
+    /// Attaches `device` at `start`, returning it to be used by the host
+    pub fn attach<D: Device + 'static>(
+        &mut self,
+        start: Word,
+        device: D,
+    ) -> ProgramResult<Shared<D>> {
+        let end = Word::try_from(device.size())
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .filter(|_| start >= 0)
+            .ok_or(ProgramError::AddressOutOfBounds(start))?;
+
+        if self.mappings.iter().any(|m| start < m.end && m.start < end) {
+            return Err(ProgramError::DeviceOverlap(start));
+        }
+
+        let shared = Arc::new(Mutex::new(device));
+        self.mappings.push(Mapping {
+            start,
+            end,
+            device: shared.clone(),
+        });
+        Ok(shared)
+    }
+
+    fn find(&self, addr: Word) -> Option<(&Mapping, usize)> {
+        self.mappings
+            .iter()
+            .find(|m| (m.start..m.end).contains(&addr))
+            .map(|m| (m, (addr - m.start) as usize))
+    }
+
+    /// Reads `addr` if a device is attached to it
+    pub fn read(&self, addr: Word) -> Option<Word> {
+        self.find(addr)
+            .map(|(m, offset)| m.device.lock().unwrap().read(offset))
+    }
+
+    /// Writes `addr` if a device is attached to it
+    pub fn write(&self, addr: Word, value: Word) -> Option<ProgramResult<()>> {
+        self.find(addr).map(|(m, offset)| {
+            if m.device.lock().unwrap().write(offset, value) {
+                Ok(())
+            } else {
+                Err(ProgramError::ReadOnlyAddress(addr))
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Devices {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.mappings.iter().map(|m| m.start..m.end))
+            .finish()
+    }
+}
+
+/// Devices are equal when the same devices are attached at the same addresses
+impl PartialEq for Devices {
+    fn eq(&self, other: &Self) -> bool {
+        self.mappings.len() == other.mappings.len()
+            && self.mappings.iter().zip(&other.mappings).all(|(a, b)| {
+                a.start == b.start && a.end == b.end && Arc::ptr_eq(&a.device, &b.device)
+            })
+    }
+}
+
+/// Reads as the time in milliseconds, since it was created or as set by the
+/// host.
+pub struct Clock {
+    started: Option<Instant>,
+    now: Word,
+}
+
+impl Clock {
+    /// A clock following the time of the host
+    pub fn new() -> Self {
+        Clock {
+            started: Some(Instant::now()),
+            now: 0,
+        }
+    }
+
+    /// A clock that only moves when the host sets it, for tests
+    pub fn manual() -> Self {
+        Clock {
+            started: None,
+            now: 0,
+        }
+    }
+
+    pub fn set(&mut self, now: Word) {
+        self.now = now;
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new()
+    }
+}
+
+impl Device for Clock {
+    fn size(&self) -> usize {
+        1
+    }
+
+    fn read(&mut self, _: usize) -> Word {
+        match self.started {
+            Some(started) => Word::try_from(started.elapsed().as_millis()).unwrap_or(Word::MAX),
+            None => self.now,
+        }
+    }
+
+    fn write(&mut self, _: usize, _: Word) -> bool {
+        false
+    }
+}
+
+/// Reads as a sequence of non-negative pseudo-random numbers below 2^31, the
+/// same for the same seed. Writing a value reseeds it.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero
+        Random { state: seed.max(1) }
+    }
+}
+
+impl Device for Random {
+    fn size(&self) -> usize {
+        1
+    }
+
+    fn read(&mut self, _: usize) -> Word {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545f4914f6cdd1d) >> 33) as Word
+    }
+
+    fn write(&mut self, _: usize, value: Word) -> bool {
+        *self = Random::new(value as u64);
+        true
+    }
+}
+
+/// A grid of pixels, row by row from the top left
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Word>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Word {
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Draws the pixels that are set as `#` and the others as `.`
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let line = row
+                .iter()
+                .map(|&pixel| if pixel == 0 { '.' } else { '#' })
+                .collect::<String>();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Device for Framebuffer {
+    fn size(&self) -> usize {
+        self.pixels.len()
+    }
+
+    fn read(&mut self, offset: usize) -> Word {
+        self.pixels[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: Word) -> bool {
+        self.pixels[offset] = value;
+        true
+    }
+}
+
+/// The keys pressed by the host, waiting to be read. The first address reads
+/// as the next key, or -1 if there is none, and the second as the number of
+/// keys waiting.
+#[derive(Default)]
+pub struct Keyboard {
+    keys: VecDeque<Word>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Keyboard::default()
+    }
+
+    pub fn press(&mut self, key: Word) {
+        self.keys.push_back(key);
+    }
+
+    /// Presses the key of every character of `text`
+    pub fn type_str(&mut self, text: &str) {
+        self.keys.extend(text.chars().map(|c| c as Word));
+    }
+}
+
+impl Device for Keyboard {
+    fn size(&self) -> usize {
+        2
+    }
+
+    fn read(&mut self, offset: usize) -> Word {
+        match offset {
+            0 => self.keys.pop_front().unwrap_or(-1),
+            _ => self.keys.len() as Word,
+        }
+    }
+
+    fn write(&mut self, _: usize, _: Word) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Program;
+    use std::io::Cursor;
+
+    /// Runs `program`, returning the values it outputs
+    fn run(program: &mut Program) -> ProgramResult<Vec<Word>> {
+        let mut outputs = vec![];
+        while !program.finished {
+            let mut out = Vec::new();
+            program.step(&mut Cursor::new(""), &mut out)?;
+            if !out.is_empty() {
+                outputs.push(String::from_utf8(out).unwrap().parse().unwrap());
+            }
+        }
+        Ok(outputs)
+    }
+
+    #[test]
+    fn test_attach_refuses_overlaps() {
+        let mut devices = Devices::default();
+
+        assert!(devices.attach(100, Framebuffer::new(2, 2)).is_ok());
+        assert_eq!(
+            Some(ProgramError::DeviceOverlap(103)),
+            devices.attach(103, Keyboard::new()).err()
+        );
+        assert_eq!(
+            Some(ProgramError::AddressOutOfBounds(-1)),
+            devices.attach(-1, Clock::manual()).err()
+        );
+        assert!(devices.attach(104, Keyboard::new()).is_ok());
+    }
+
+    #[test]
+    fn test_clock() {
+        // OUT [100]; OUT [100]; FIN
+        let mut program = Program::new(vec![4, 100, 4, 100, 99]);
+        let clock = program.devices.attach(100, Clock::manual()).unwrap();
+        clock.lock().unwrap().set(1234);
+
+        assert_eq!(Ok(vec![1234, 1234]), run(&mut program));
+    }
+
+    #[test]
+    fn test_random_is_seeded() {
+        // OUT [100]; OUT [100]; FIN
+        let code = vec![4, 100, 4, 100, 99];
+        let outputs = (0..2)
+            .map(|_| {
+                let mut program = Program::new(code.clone());
+                program.devices.attach(100, Random::new(7)).unwrap();
+                run(&mut program).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(outputs[0], outputs[1]);
+        assert_ne!(outputs[0][0], outputs[0][1]);
+        assert!(outputs[0].iter().all(|value| (0..1 << 31).contains(value)));
+    }
+
+    #[test]
+    fn test_framebuffer() {
+        // ADD #1, #0, [101]; ADD #2, #0, [102]; FIN
+        let mut program = Program::new(vec![1101, 1, 0, 101, 1101, 2, 0, 102, 99]);
+        let screen = program.devices.attach(100, Framebuffer::new(2, 2)).unwrap();
+
+        assert_eq!(Ok(vec![]), run(&mut program));
+        let screen = screen.lock().unwrap();
+        assert_eq!(2, screen.pixel(0, 1));
+        assert_eq!(".#\n#.\n", screen.to_string());
+        assert_eq!(9, program.code.len());
+    }
+
+    #[test]
+    fn test_keyboard() {
+        // OUT [101]; OUT [100]; OUT [100]; OUT [100]; FIN
+        let mut program = Program::new(vec![4, 101, 4, 100, 4, 100, 4, 100, 99]);
+        let keyboard = program.devices.attach(100, Keyboard::new()).unwrap();
+        keyboard.lock().unwrap().type_str("hi");
+
+        assert_eq!(Ok(vec![2, 104, 105, -1]), run(&mut program));
+    }
+
+    #[test]
+    fn test_read_only_devices() {
+        // ADD #1, #1, [100]; FIN
+        let mut program = Program::new(vec![1101, 1, 1, 100, 99]);
+        program.devices.attach(100, Keyboard::new()).unwrap();
+
+        assert_eq!(Err(ProgramError::ReadOnlyAddress(100)), run(&mut program));
+    }
+}
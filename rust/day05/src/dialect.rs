@@ -0,0 +1,149 @@
+//! The instruction sets of the Intcode machine as each puzzle specified them,
+//! so that an image keeps the semantics of the day it was written for.
+use super::opcodes::{self, Operation};
+use super::{Opcode, ParameterMode, ProgramError, ProgramResult, Word};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dialect {
+    /// The opcodes that may be executed, as the last two digits of the
+    /// instruction
+    pub opcodes: &'static [Word],
+    pub modes: &'static [ParameterMode],
+    /// Every value written to memory must fit in a signed integer of this
+    /// many bits, so none does if it is 0
+    pub word_bits: u32,
+    /// Whether memory beyond the end of the image reads as zero and grows
+    /// when written, instead of being out of bounds
+    pub extended_memory: bool,
+    /// Operations added on top of the built in ones, taking precedence over
+    /// them
+    pub extensions: Vec<Operation>,
+}
+impl Dialect {
+    /// Day 2: `Add`, `Mul` and `Fin`, with every parameter being a position
+    /// https://adventofcode.com/2019/day/2
+    pub const DAY02: Dialect = Dialect {
+        opcodes: &[1, 2, 99],
+        modes: &[ParameterMode::Position],
+        word_bits: 32,
+        extended_memory: false,
+        extensions: Vec::new(),
+    };
+
+    /// Day 5: adds I/O, jumps, comparisons and the immediate parameter mode
+    /// https://adventofcode.com/2019/day/5
+    pub const DAY05: Dialect = Dialect {
+        opcodes: &[1, 2, 3, 4, 5, 6, 7, 8, 99],
+        modes: &[ParameterMode::Position, ParameterMode::Immediate],
+        word_bits: 32,
+        extended_memory: false,
+        extensions: Vec::new(),
+    };
+
+    /// Day 9: adds the relative parameter mode and `Arb`, 64 bit words and
+    /// memory beyond the end of the image
+    /// https://adventofcode.com/2019/day/9
+    pub const DAY09: Dialect = Dialect {
+        opcodes: &[1, 2, 3, 4, 5, 6, 7, 8, 9, 99],
+        modes: &[
+            ParameterMode::Position,
+            ParameterMode::Immediate,
+            ParameterMode::Relative,
+        ],
+        word_bits: 64,
+        extended_memory: true,
+        extensions: Vec::new(),
+    };
+
+    /// Adds `operation` to the dialect, replacing any operation with the same
+    /// number.
+    pub fn register(&mut self, operation: Operation) {
+        self.extensions.retain(|op| op.number != operation.number);
+        self.extensions.push(operation);
+    }
+
+    pub fn operation(&self, number: Word) -> Option<&Operation> {
+        opcodes::lookup(number, &self.extensions)
+    }
+
+    pub fn check(&self, opcode: &Opcode) -> ProgramResult<()> {
+        if !self.opcodes.contains(&opcode.number)
+            && !self.extensions.iter().any(|op| op.number == opcode.number)
+        {
+            return Err(ProgramError::UnsupportedOpcode(opcode.number));
+        }
+
+        match opcode.modes.iter().find(|m| !self.modes.contains(m)) {
+            Some(&mode) => Err(ProgramError::UnsupportedParameterMode(mode)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn fits(&self, value: Word) -> bool {
+        match self.word_bits.checked_sub(1) {
+            None => false,
+            Some(bits) if bits >= 63 => true,
+            Some(bits) => {
+                let max = 1 << bits;
+                -max <= value && value < max
+            }
+        }
+    }
+}
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect::DAY05
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ParameterMode::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(
+            Ok(()),
+            Dialect::DAY02.check(&Opcode::new(opcodes::ADD, &[Position, Position, Position]))
+        );
+        assert_eq!(
+            Ok(()),
+            Dialect::DAY02.check(&Opcode::new(opcodes::FIN, &[]))
+        );
+        assert_eq!(
+            Err(ProgramError::UnsupportedOpcode(3)),
+            Dialect::DAY02.check(&Opcode::new(opcodes::IN, &[Position]))
+        );
+        assert_eq!(
+            Err(ProgramError::UnsupportedParameterMode(Immediate)),
+            Dialect::DAY02.check(&Opcode::new(opcodes::MUL, &[Position, Immediate, Position]))
+        );
+        assert_eq!(
+            Ok(()),
+            Dialect::DAY05.check(&Opcode::new(opcodes::JT, &[Immediate, Position]))
+        );
+    }
+
+    #[test]
+    fn test_fits() {
+        assert!(Dialect::DAY05.fits(2147483647));
+        assert!(Dialect::DAY05.fits(-2147483648));
+        assert!(!Dialect::DAY05.fits(2147483648));
+        assert!(!Dialect::DAY05.fits(-2147483649));
+
+        let dialect = Dialect {
+            word_bits: 64,
+            ..Dialect::DAY05
+        };
+        assert!(dialect.fits(Word::MAX));
+        assert!(dialect.fits(Word::MIN));
+
+        let dialect = Dialect {
+            word_bits: 0,
+            ..Dialect::DAY05
+        };
+        assert!(!dialect.fits(0));
+    }
+}
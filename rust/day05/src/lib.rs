@@ -0,0 +1,1866 @@
+//! The Intcode machine of day 5 of Advent of Code 2019
+//! https://adventofcode.com/2019/day/5
+mod async_program;
+pub mod coverage;
+pub mod dap;
+pub mod decompile;
+pub mod devices;
+mod dialect;
+pub mod loader;
+pub mod opcodes;
+pub mod optimize;
+pub mod session;
+pub mod solution;
+pub mod wasm;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::prelude::*;
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+use devices::Devices;
+pub use dialect::Dialect;
+use opcodes::{Flow, Operation};
+
+pub type Pc = usize;
+pub type Word = i64;
+pub type Code = Vec<Word>;
+
+#[derive(Debug, PartialEq)]
+pub enum ProgramError {
+    UnknownOpcode(Word),
+    UnknownParameterMode(Word),
+    /// The instruction needs more parameters than there is code left
+    TruncatedInstr,
+    PcOutOfBounds(Pc),
+    AddressOutOfBounds(Word),
+    Overflow,
+    InvalidInput(String),
+    Io(std::io::ErrorKind),
+    /// More instructions were executed than `Limits::max_steps` allows
+    StepLimitExceeded(u64),
+    /// The memory is larger than `Limits::max_memory` allows
+    MemoryLimitExceeded(usize),
+    /// More values were output than `Limits::max_outputs` allows
+    OutputLimitExceeded(usize),
+    /// The program ran for longer than `Limits::timeout` allows
+    Timeout(Duration),
+    /// `In` was executed after the input channel was closed
+    InputClosed,
+    /// `Out` was executed after the output channel was closed
+    OutputClosed,
+    /// The opcode is not part of the dialect of the program
+    UnsupportedOpcode(Word),
+    /// The parameter mode is not part of the dialect of the program
+    UnsupportedParameterMode(ParameterMode),
+    /// A device was attached over addresses taken by another device
+    DeviceOverlap(Word),
+    /// The address belongs to a device that can't be written
+    ReadOnlyAddress(Word),
+}
+
+pub type ProgramResult<T> = Result<T, ProgramError>;
+
+/// A program failing is a failure of the solver, for the binaries
+impl From<ProgramError> for aoc::RunError {
+    fn from(e: ProgramError) -> Self {
+        aoc::RunError::Solver(format!("Could not execute instruction: {}", e))
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::UnknownOpcode(n) => write!(f, "unknown opcode {}", n),
+            ProgramError::UnknownParameterMode(n) => write!(f, "unknown parameter mode {}", n),
+            ProgramError::TruncatedInstr => write!(f, "instruction truncated by the end of code"),
+            ProgramError::PcOutOfBounds(pc) => write!(f, "pc {} is out of bounds", pc),
+            ProgramError::AddressOutOfBounds(addr) => {
+                write!(f, "address {} is out of bounds", addr)
+            }
+            ProgramError::Overflow => write!(f, "arithmetic overflow"),
+            ProgramError::InvalidInput(input) => write!(f, "invalid input {:?}", input),
+            ProgramError::Io(kind) => write!(f, "{:?}", kind),
+            ProgramError::StepLimitExceeded(n) => write!(f, "exceeded the limit of {} steps", n),
+            ProgramError::MemoryLimitExceeded(n) => {
+                write!(f, "exceeded the limit of {} words of memory", n)
+            }
+            ProgramError::OutputLimitExceeded(n) => {
+                write!(f, "exceeded the limit of {} outputs", n)
+            }
+            ProgramError::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            ProgramError::InputClosed => write!(f, "input closed"),
+            ProgramError::OutputClosed => write!(f, "output closed"),
+            ProgramError::UnsupportedOpcode(n) => {
+                write!(f, "opcode {} is not part of the dialect", n)
+            }
+            ProgramError::UnsupportedParameterMode(mode) => {
+                write!(f, "parameter mode {:?} is not part of the dialect", mode)
+            }
+            ProgramError::DeviceOverlap(addr) => {
+                write!(f, "device at {} overlaps another device", addr)
+            }
+            ProgramError::ReadOnlyAddress(addr) => write!(f, "address {} is read-only", addr),
+        }
+    }
+}
+
+impl From<std::io::Error> for ProgramError {
+    fn from(e: std::io::Error) -> Self {
+        ProgramError::Io(e.kind())
+    }
+}
+
+/// The first word of an instruction: the number of the operation and the
+/// mode of each of its parameters
+#[derive(Debug, PartialEq, Clone)]
+pub struct Opcode {
+    pub number: Word,
+    pub modes: Vec<ParameterMode>,
+}
+impl Opcode {
+    pub fn new(number: Word, modes: &[ParameterMode]) -> Self {
+        Opcode {
+            number,
+            modes: modes.to_vec(),
+        }
+    }
+
+    /// Decodes an opcode of one of the built in operations
+    pub fn decode(i: Word) -> ProgramResult<Opcode> {
+        Opcode::decode_with(i, &[])
+    }
+
+    /// Decodes an opcode of one of `extensions` or the built in operations
+    pub fn decode_with(i: Word, extensions: &[Operation]) -> ProgramResult<Opcode> {
+        // Pick out the last two digits to determine the opcode
+        let number = i - 100 * (i / 100);
+        let operation =
+            opcodes::lookup(number, extensions).ok_or(ProgramError::UnknownOpcode(i))?;
+
+        let modes = (0..operation.arity as u32)
+            .map(|idx| {
+                let ii = mode_place(idx).map_or(0, |place| i / place);
+                ParameterMode::parse(ii - 10 * (ii / 10))
+            })
+            .collect::<ProgramResult<_>>()?;
+
+        Ok(Opcode { number, modes })
+    }
+
+    /// The inverse of `decode`
+    pub fn encode(&self) -> Word {
+        self.number
+            + self
+                .modes
+                .iter()
+                .enumerate()
+                .map(|(idx, mode)| mode_place(idx as u32).map_or(0, |place| place * mode.encode()))
+                .sum::<Word>()
+    }
+}
+
+/// The place value of the digit holding the mode of parameter `idx`, if it
+/// fits in a word
+fn mode_place(idx: u32) -> Option<Word> {
+    10i64.checked_pow(idx).and_then(|p| p.checked_mul(100))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Instr(pub Opcode, pub Vec<Word>);
+impl Instr {
+    pub fn decode(code: &[Word]) -> ProgramResult<Instr> {
+        Instr::decode_with(code, &[])
+    }
+
+    pub fn decode_with(code: &[Word], extensions: &[Operation]) -> ProgramResult<Instr> {
+        let opcode = Opcode::decode_with(
+            *code.first().ok_or(ProgramError::TruncatedInstr)?,
+            extensions,
+        )?;
+        let params = code
+            .get(1..=opcode.modes.len())
+            .ok_or(ProgramError::TruncatedInstr)?;
+
+        Ok(Instr(opcode, params.to_vec()))
+    }
+
+    pub fn encode(&self) -> Vec<Word> {
+        [&[self.0.encode()], &self.1[..]].concat()
+    }
+
+    /// The number of words the instruction occupies
+    pub fn size(&self) -> usize {
+        1 + self.1.len()
+    }
+}
+
+/// Formats the instruction as assembly, such as `ADD #7, [rb+5], [6]`
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Instr(opcode, params) = self;
+
+        match opcodes::lookup(opcode.number, &[]) {
+            Some(operation) => write!(f, "{}", operation.mnemonic)?,
+            None => write!(f, "OP{}", opcode.number)?,
+        }
+
+        for (idx, (mode, param)) in opcode.modes.iter().zip(params).enumerate() {
+            f.write_str(if idx == 0 { " " } else { ", " })?;
+            match mode {
+                ParameterMode::Position => write!(f, "[{}]", param)?,
+                ParameterMode::Immediate => write!(f, "#{}", param)?,
+                ParameterMode::Relative => write!(f, "[rb{:+}]", param)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParameterMode {
+    Position,
+    Immediate,
+    /// A position relative to the relative base of the program
+    Relative,
+}
+impl ParameterMode {
+    fn parse(n: Word) -> ProgramResult<ParameterMode> {
+        match n {
+            0 => Ok(ParameterMode::Position),
+            1 => Ok(ParameterMode::Immediate),
+            2 => Ok(ParameterMode::Relative),
+            _ => Err(ProgramError::UnknownParameterMode(n)),
+        }
+    }
+
+    fn encode(&self) -> Word {
+        match self {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        }
+    }
+}
+
+/// Bounds on a run of a `Program`, for when the image can't be trusted to
+/// halt on its own. `None` means unbounded.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub max_memory: Option<usize>,
+    pub max_outputs: Option<usize>,
+    /// Checked between instructions, so a program blocked on `In` is not
+    /// interrupted.
+    pub timeout: Option<Duration>,
+}
+impl Limits {
+    /// Sets the limit of a command line option, one of `--max-steps <n>`,
+    /// `--max-memory <words>`, `--max-outputs <n>` or `--timeout <seconds>`.
+    /// Returns `false` if `option` is none of them.
+    pub fn set_option(&mut self, option: &str, value: &str) -> Result<bool, String> {
+        let invalid = || format!("Invalid {} {}, expected a number", option, value);
+
+        match option {
+            "--max-steps" => self.max_steps = Some(value.parse().map_err(|_| invalid())?),
+            "--max-memory" => self.max_memory = Some(value.parse().map_err(|_| invalid())?),
+            "--max-outputs" => self.max_outputs = Some(value.parse().map_err(|_| invalid())?),
+            "--timeout" => {
+                let seconds = value.parse().map_err(|_| invalid())?;
+                self.timeout = Some(Duration::try_from_secs_f64(seconds).map_err(|_| invalid())?)
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// The steps and outputs of a run so far, checked against its `Limits`
+struct Budget<'a> {
+    limits: &'a Limits,
+    started: Instant,
+    steps: u64,
+    outputs: usize,
+}
+impl<'a> Budget<'a> {
+    fn new(limits: &'a Limits) -> Self {
+        Budget {
+            limits,
+            started: Instant::now(),
+            steps: 0,
+            outputs: 0,
+        }
+    }
+
+    /// Decodes the next instruction of `program`, unless executing it would
+    /// go beyond one of the limits
+    fn next(&mut self, program: &Program) -> ProgramResult<Instr> {
+        let limits = self.limits;
+        if let Some(max_memory) = limits.max_memory {
+            if program.code.len() > max_memory {
+                return Err(ProgramError::MemoryLimitExceeded(max_memory));
+            }
+        }
+        if let Some(timeout) = limits.timeout {
+            if self.started.elapsed() > timeout {
+                return Err(ProgramError::Timeout(timeout));
+            }
+        }
+
+        if let Some(max_steps) = limits.max_steps {
+            if self.steps >= max_steps {
+                return Err(ProgramError::StepLimitExceeded(max_steps));
+            }
+        }
+        self.steps += 1;
+
+        let instr = program.decode()?;
+
+        if instr.0.number == opcodes::OUT {
+            if let Some(max_outputs) = limits.max_outputs {
+                if self.outputs >= max_outputs {
+                    return Err(ProgramError::OutputLimitExceeded(max_outputs));
+                }
+            }
+            self.outputs += 1;
+        }
+
+        Ok(instr)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Program {
+    pub pc: Pc,
+    pub code: Code,
+    pub finished: bool,
+    pub dialect: Dialect,
+    pub relative_base: Word,
+    pub devices: Devices,
+    /// The most words the memory may grow to with extended memory, set by
+    /// `run` from `Limits::max_memory`
+    pub max_memory: Option<usize>,
+}
+impl Program {
+    pub fn new(code: Code) -> Self {
+        Self::with_dialect(code, Dialect::default())
+    }
+
+    pub fn with_dialect(code: Code, dialect: Dialect) -> Self {
+        Self {
+            code,
+            pc: 0,
+            finished: false,
+            dialect,
+            relative_base: 0,
+            devices: Devices::default(),
+            max_memory: None,
+        }
+    }
+
+    pub fn tick(&mut self) -> ProgramResult<()> {
+        self.step(&mut std::io::stdin().lock(), &mut std::io::stdout())
+    }
+
+    /// Decodes and executes the instruction at `pc`, reading and writing
+    /// through `r#in` and `out` instead of the terminal.
+    pub fn step(&mut self, r#in: &mut dyn BufRead, out: &mut dyn Write) -> ProgramResult<()> {
+        let instr = self.decode()?;
+        self.execute_instr(instr, r#in, out)
+    }
+
+    /// Steps the program like `step`, returning the value the instruction
+    /// output, if any.
+    pub fn step_collecting(&mut self, r#in: &mut dyn BufRead) -> ProgramResult<Option<Word>> {
+        collect_output(|out| self.step(r#in, out))
+    }
+
+    /// Steps the program until it finishes or goes beyond one of `limits`.
+    pub fn run(
+        &mut self,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+        limits: &Limits,
+    ) -> ProgramResult<()> {
+        self.run_with(r#in, out, limits, |_, _| {})
+    }
+
+    /// Like `run`, but calls `observe` with each instruction right before it
+    /// is executed.
+    pub fn run_with<F>(
+        &mut self,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+        limits: &Limits,
+        mut observe: F,
+    ) -> ProgramResult<()>
+    where
+        F: FnMut(&Program, &Instr),
+    {
+        self.run_each(limits, |program, instr| {
+            observe(program, &instr);
+            program.execute_instr(instr, r#in, out)
+        })
+    }
+
+    /// Runs the program like `run`, returning the values it output.
+    pub fn run_collecting(
+        &mut self,
+        r#in: &mut dyn BufRead,
+        limits: &Limits,
+    ) -> ProgramResult<Vec<Word>> {
+        let mut outputs = Vec::new();
+        self.run_each(limits, |program, instr| {
+            outputs.extend(collect_output(|out| {
+                program.execute_instr(instr, r#in, out)
+            })?);
+            Ok(())
+        })?;
+        Ok(outputs)
+    }
+
+    /// Decodes each instruction until the program finishes or goes beyond
+    /// one of `limits`, leaving it to `execute`.
+    fn run_each<F>(&mut self, limits: &Limits, mut execute: F) -> ProgramResult<()>
+    where
+        F: FnMut(&mut Program, Instr) -> ProgramResult<()>,
+    {
+        let mut budget = Budget::new(limits);
+        self.max_memory = limits.max_memory;
+
+        while !self.finished {
+            let instr = budget.next(self)?;
+            execute(self, instr)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode(&self) -> ProgramResult<Instr> {
+        let code = self
+            .code
+            .get(self.pc..)
+            .ok_or(ProgramError::PcOutOfBounds(self.pc))?;
+        let instr = Instr::decode_with(code, &self.dialect.extensions)?;
+
+        self.dialect.check(&instr.0)?;
+        Ok(instr)
+    }
+
+    /// Reads the word at `addr`. Beyond the end of the code it reads as zero
+    /// if the dialect has extended memory.
+    pub fn read(&self, addr: Word) -> ProgramResult<Word> {
+        if let Some(value) = self.devices.read(addr) {
+            return Ok(value);
+        }
+
+        let a = usize::try_from(addr).map_err(|_| ProgramError::AddressOutOfBounds(addr))?;
+        match self.code.get(a) {
+            Some(&word) => Ok(word),
+            None if self.dialect.extended_memory => Ok(0),
+            None => Err(ProgramError::AddressOutOfBounds(addr)),
+        }
+    }
+
+    /// Writes `value` to `addr`, provided it fits in a word of the dialect.
+    /// Beyond the end of the code the memory grows to fit if the dialect has
+    /// extended memory.
+    pub fn write(&mut self, addr: Word, value: Word) -> ProgramResult<()> {
+        if !self.dialect.fits(value) {
+            return Err(ProgramError::Overflow);
+        }
+        if let Some(result) = self.devices.write(addr, value) {
+            return result;
+        }
+
+        let a = usize::try_from(addr).map_err(|_| ProgramError::AddressOutOfBounds(addr))?;
+        if a >= self.code.len() && self.dialect.extended_memory {
+            self.grow(addr, a)?;
+        }
+
+        let cell = self
+            .code
+            .get_mut(a)
+            .ok_or(ProgramError::AddressOutOfBounds(addr))?;
+        *cell = value;
+        Ok(())
+    }
+
+    /// Grows the memory up to `a`, failing instead of going beyond
+    /// `max_memory` or what can be allocated
+    fn grow(&mut self, addr: Word, a: usize) -> ProgramResult<()> {
+        let size = a
+            .checked_add(1)
+            .ok_or(ProgramError::AddressOutOfBounds(addr))?;
+        if let Some(max_memory) = self.max_memory {
+            if size > max_memory {
+                return Err(ProgramError::MemoryLimitExceeded(max_memory));
+            }
+        }
+
+        self.code
+            .try_reserve_exact(size - self.code.len())
+            .map_err(|_| ProgramError::AddressOutOfBounds(addr))?;
+        self.code.resize(size, 0);
+        Ok(())
+    }
+
+    /// The address a parameter in position or relative mode refers to
+    fn address(&self, mode: ParameterMode, param: Word) -> ProgramResult<Word> {
+        match mode {
+            ParameterMode::Relative => self
+                .relative_base
+                .checked_add(param)
+                .ok_or(ProgramError::Overflow),
+            _ => Ok(param),
+        }
+    }
+
+    fn param_value(&self, mode: ParameterMode, param: Word) -> ProgramResult<Word> {
+        match mode {
+            ParameterMode::Immediate => Ok(param),
+            _ => self.read(self.address(mode, param)?),
+        }
+    }
+
+    fn execute_instr(
+        &mut self,
+        instr: Instr,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+    ) -> ProgramResult<()> {
+        let Instr(opcode, params) = instr;
+        let operation = *self
+            .dialect
+            .operation(opcode.number)
+            .ok_or(ProgramError::UnknownOpcode(opcode.number))?;
+
+        let operands = params
+            .iter()
+            .zip(&opcode.modes)
+            .enumerate()
+            .map(|(idx, (&param, &mode))| {
+                if operation.writes_to(idx) {
+                    self.address(mode, param)
+                } else {
+                    self.param_value(mode, param)
+                }
+            })
+            .collect::<ProgramResult<Vec<_>>>()?;
+
+        match (operation.handler)(self, &operands, r#in, out)? {
+            Flow::Next => self.pc += 1 + params.len(),
+            Flow::Jump(target) => {
+                self.pc =
+                    usize::try_from(target).map_err(|_| ProgramError::AddressOutOfBounds(target))?
+            }
+            Flow::Halt => self.finished = true,
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs `step`, a single instruction writing to `out`, and returns the value
+/// it output, if any. Values are written without separators, so each
+/// instruction gets its own buffer to tell consecutive outputs apart.
+pub fn collect_output<F>(step: F) -> ProgramResult<Option<Word>>
+where
+    F: FnOnce(&mut dyn Write) -> ProgramResult<()>,
+{
+    let mut out = Vec::new();
+    step(&mut out)?;
+
+    let out = String::from_utf8_lossy(&out);
+    Ok(out.trim_start_matches("Input: ").parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::opcodes::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_execute_instr_add() {
+        let mut program = Program::new(vec![0, 1, 2, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        ADD,
+                        &[
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![1, 2, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                code: vec![3, 1, 2, 0],
+                pc: 4,
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_mul() {
+        let mut program = Program::new(vec![0, 4, 5, 0, 5, 6]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        MUL,
+                        &[
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![4, 5, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![30, 4, 5, 0, 5, 6],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_mul_neg() {
+        let mut program = Program::new(vec![0, -4, 5]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        MUL,
+                        &[
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![1, 2, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![-20, -4, 5],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_fin() {
+        let mut program = Program::new(vec![99]);
+
+        program
+            .execute_instr(
+                Instr(Opcode::new(FIN, &[]), vec![]),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                code: vec![99],
+                pc: 0,
+                finished: true,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_in() {
+        let mut program = Program::new(vec![3, 1]);
+
+        program
+            .execute_instr(
+                Instr(Opcode::new(IN, &[ParameterMode::Position]), vec![1]),
+                &mut Cursor::new("89"),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                code: vec![3, 89],
+                pc: 2,
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_out() {
+        let mut program = Program::new(vec![4, 10]);
+        let mut buf = Cursor::new(Vec::new());
+
+        program
+            .execute_instr(
+                Instr(Opcode::new(OUT, &[ParameterMode::Position]), vec![1]),
+                &mut Cursor::new(""),
+                &mut buf,
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                code: vec![4, 10],
+                pc: 2,
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+        assert_eq!("10", std::str::from_utf8(buf.get_ref()).unwrap());
+    }
+
+    #[test]
+    fn test_execute_instr_jt_jump_taken() {
+        let mut program = Program::new(vec![5, 1, 7]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(JT, &[ParameterMode::Immediate, ParameterMode::Immediate]),
+                    vec![1, 7],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 7,
+                code: vec![5, 1, 7],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_jt_jump_not_taken() {
+        let mut program = Program::new(vec![5, 0, 7]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(JT, &[ParameterMode::Immediate, ParameterMode::Immediate]),
+                    vec![0, 7],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 3,
+                code: vec![5, 0, 7],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_jf_jump_taken() {
+        let mut program = Program::new(vec![5, 0, 7]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(JF, &[ParameterMode::Immediate, ParameterMode::Immediate]),
+                    vec![0, 7],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 7,
+                code: vec![5, 0, 7],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_jf_jump_not_taken() {
+        let mut program = Program::new(vec![6, 1, 7]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(JF, &[ParameterMode::Immediate, ParameterMode::Immediate]),
+                    vec![1, 7],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 3,
+                code: vec![6, 1, 7],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_lt_true() {
+        let mut program = Program::new(vec![8, 1, 7, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        LT,
+                        &[
+                            ParameterMode::Immediate,
+                            ParameterMode::Immediate,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![1, 7, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![1, 1, 7, 0],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_lt_false() {
+        let mut program = Program::new(vec![8, 8, 7, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        LT,
+                        &[
+                            ParameterMode::Immediate,
+                            ParameterMode::Immediate,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![8, 7, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![0, 8, 7, 0],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_eq_true() {
+        let mut program = Program::new(vec![9, 1, 1, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        EQ,
+                        &[
+                            ParameterMode::Immediate,
+                            ParameterMode::Immediate,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![1, 1, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![1, 1, 1, 0],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_execute_instr_eq_false() {
+        let mut program = Program::new(vec![9, 8, 7, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        EQ,
+                        &[
+                            ParameterMode::Immediate,
+                            ParameterMode::Immediate,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![8, 7, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                pc: 4,
+                code: vec![0, 8, 7, 0],
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        let expected = vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50];
+        let mut program = Program::new(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+
+        program.tick().unwrap();
+        program.tick().unwrap();
+        program.tick().unwrap();
+
+        assert_eq!(
+            Program {
+                code: expected,
+                pc: 8,
+                finished: true,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn execute_instr_with_immediate_mode() {
+        let mut program = Program::new(vec![11100, 10, 20, 0]);
+
+        program
+            .execute_instr(
+                Instr(
+                    Opcode::new(
+                        ADD,
+                        &[
+                            ParameterMode::Immediate,
+                            ParameterMode::Immediate,
+                            ParameterMode::Position,
+                        ],
+                    ),
+                    vec![10, 20, 0],
+                ),
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Program {
+                code: vec![30, 10, 20, 0],
+                pc: 4,
+                finished: false,
+                dialect: Dialect::DAY05,
+                relative_base: 0,
+                devices: Devices::default(),
+                max_memory: None,
+            },
+            program
+        );
+    }
+
+    #[test]
+    fn test_step_collecting() {
+        // IN [5]; OUT [5]; FIN
+        let mut program = Program::new(vec![3, 5, 4, 5, 99, 0]);
+        let mut r#in = Cursor::new("-7\n");
+
+        assert_eq!(Ok(None), program.step_collecting(&mut r#in));
+        assert_eq!(Ok(Some(-7)), program.step_collecting(&mut r#in));
+        assert_eq!(Ok(None), program.step_collecting(&mut r#in));
+        assert!(program.finished);
+    }
+
+    #[test]
+    fn test_run_collecting() {
+        // OUT #1; OUT #23; FIN
+        let code = vec![104, 1, 104, 23, 99];
+        let limits = Limits {
+            max_outputs: Some(1),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Ok(vec![1, 23]),
+            Program::new(code.clone()).run_collecting(&mut Cursor::new(""), &Limits::default())
+        );
+        assert_eq!(
+            Err(ProgramError::OutputLimitExceeded(1)),
+            Program::new(code).run_collecting(&mut Cursor::new(""), &limits)
+        );
+    }
+}
+
+#[cfg(test)]
+mod opcode_tests {
+
+    use super::*;
+    use crate::opcodes::*;
+
+    #[test]
+    fn test_decode_opcode() {
+        assert_eq!(
+            Ok(Opcode::new(
+                ADD,
+                &[
+                    ParameterMode::Position,
+                    ParameterMode::Position,
+                    ParameterMode::Position
+                ]
+            )),
+            Opcode::decode(1)
+        );
+        assert_eq!(
+            Ok(Opcode::new(
+                MUL,
+                &[
+                    ParameterMode::Position,
+                    ParameterMode::Position,
+                    ParameterMode::Position
+                ]
+            )),
+            Opcode::decode(2)
+        );
+        assert_eq!(
+            Ok(Opcode::new(IN, &[ParameterMode::Position])),
+            Opcode::decode(3)
+        );
+        assert_eq!(
+            Ok(Opcode::new(OUT, &[ParameterMode::Position])),
+            Opcode::decode(4)
+        );
+        assert_eq!(Ok(Opcode::new(FIN, &[])), Opcode::decode(99));
+        assert_eq!(
+            Ok(Opcode::new(ARB, &[ParameterMode::Position])),
+            Opcode::decode(9)
+        );
+        assert_eq!(Err(ProgramError::UnknownOpcode(10)), Opcode::decode(10));
+        assert_eq!(
+            Ok(Opcode::new(
+                JT,
+                &[ParameterMode::Position, ParameterMode::Position]
+            )),
+            Opcode::decode(5)
+        );
+        assert_eq!(
+            Ok(Opcode::new(
+                JF,
+                &[ParameterMode::Position, ParameterMode::Position]
+            )),
+            Opcode::decode(6)
+        );
+        assert_eq!(
+            Ok(Opcode::new(
+                LT,
+                &[
+                    ParameterMode::Position,
+                    ParameterMode::Position,
+                    ParameterMode::Position
+                ]
+            )),
+            Opcode::decode(7)
+        );
+    }
+
+    #[test]
+    fn test_decode_opcode_errors() {
+        assert_eq!(Err(ProgramError::UnknownOpcode(-5)), Opcode::decode(-5));
+        assert_eq!(
+            Err(ProgramError::UnknownParameterMode(7)),
+            Opcode::decode(701)
+        );
+    }
+
+    #[test]
+    fn test_encode_opcode() {
+        assert_eq!(
+            1,
+            Opcode::new(
+                ADD,
+                &[
+                    ParameterMode::Position,
+                    ParameterMode::Position,
+                    ParameterMode::Position
+                ]
+            )
+            .encode()
+        );
+        assert_eq!(
+            1101,
+            Opcode::new(
+                ADD,
+                &[
+                    ParameterMode::Immediate,
+                    ParameterMode::Immediate,
+                    ParameterMode::Position
+                ]
+            )
+            .encode()
+        );
+        assert_eq!(104, Opcode::new(OUT, &[ParameterMode::Immediate]).encode());
+        assert_eq!(99, Opcode::new(FIN, &[]).encode());
+    }
+
+    #[test]
+    fn test_decode_opcode_with_parameters() {
+        assert_eq!(
+            Ok(Opcode::new(
+                ADD,
+                &[
+                    ParameterMode::Position,
+                    ParameterMode::Immediate,
+                    ParameterMode::Immediate
+                ]
+            )),
+            Opcode::decode(11001)
+        );
+    }
+}
+
+#[cfg(test)]
+mod instr_tests {
+
+    use super::*;
+    use crate::opcodes::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "ADD #7, [rb+5], [6]",
+            Instr::decode(&[2101, 7, 5, 6]).unwrap().to_string()
+        );
+        assert_eq!(
+            "JF [rb-2], #0",
+            Instr::decode(&[1206, -2, 0]).unwrap().to_string()
+        );
+        assert_eq!("FIN", Instr::decode(&[99]).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(
+                    ADD,
+                    &[
+                        ParameterMode::Position,
+                        ParameterMode::Position,
+                        ParameterMode::Position
+                    ]
+                ),
+                vec![2, 3, 4]
+            )),
+            Instr::decode(&[1, 2, 3, 4])
+        );
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(
+                    MUL,
+                    &[
+                        ParameterMode::Position,
+                        ParameterMode::Position,
+                        ParameterMode::Position
+                    ]
+                ),
+                vec![3, 4, 5]
+            )),
+            Instr::decode(&[2, 3, 4, 5])
+        );
+        assert_eq!(
+            Ok(Instr(Opcode::new(FIN, &[]), vec![])),
+            Instr::decode(&[99])
+        );
+        assert_eq!(
+            Ok(Instr(Opcode::new(IN, &[ParameterMode::Position]), vec![1])),
+            Instr::decode(&[3, 1])
+        );
+        assert_eq!(
+            Ok(Instr(Opcode::new(OUT, &[ParameterMode::Position]), vec![2])),
+            Instr::decode(&[4, 2])
+        );
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(JT, &[ParameterMode::Position, ParameterMode::Position]),
+                vec![2, 3]
+            )),
+            Instr::decode(&[5, 2, 3])
+        );
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(JF, &[ParameterMode::Position, ParameterMode::Position]),
+                vec![2, 3]
+            )),
+            Instr::decode(&[6, 2, 3])
+        );
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(
+                    LT,
+                    &[
+                        ParameterMode::Position,
+                        ParameterMode::Position,
+                        ParameterMode::Position
+                    ]
+                ),
+                vec![2, 3, 4]
+            )),
+            Instr::decode(&[7, 2, 3, 4])
+        );
+        assert_eq!(
+            Ok(Instr(
+                Opcode::new(
+                    EQ,
+                    &[
+                        ParameterMode::Position,
+                        ParameterMode::Position,
+                        ParameterMode::Position
+                    ]
+                ),
+                vec![2, 3, 4]
+            )),
+            Instr::decode(&[8, 2, 3, 4])
+        );
+    }
+}
+
+#[cfg(test)]
+mod program_error_tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(code: Code, input: &str) -> ProgramResult<()> {
+        let mut program = Program::new(code);
+        let mut r#in = Cursor::new(input.to_string());
+
+        while !program.finished {
+            program.step(&mut r#in, &mut Cursor::new(Vec::new()))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_instr() {
+        assert_eq!(Err(ProgramError::TruncatedInstr), run(vec![1, 0, 0], ""));
+    }
+
+    #[test]
+    fn test_pc_out_of_bounds() {
+        assert_eq!(
+            Err(ProgramError::PcOutOfBounds(9)),
+            run(vec![1105, 1, 9], "")
+        );
+    }
+
+    #[test]
+    fn test_address_out_of_bounds() {
+        assert_eq!(
+            Err(ProgramError::AddressOutOfBounds(-1)),
+            run(vec![1, -1, 0, 0, 99], "")
+        );
+        assert_eq!(
+            Err(ProgramError::AddressOutOfBounds(5)),
+            run(vec![1101, 1, 1, 5, 99], "")
+        );
+        assert_eq!(
+            Err(ProgramError::AddressOutOfBounds(-3)),
+            run(vec![1105, 1, -3], "")
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            Err(ProgramError::Overflow),
+            run(vec![1102, 2147483647, 2, 0, 99], "")
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            Err(ProgramError::InvalidInput("x".to_string())),
+            run(vec![3, 0, 99], "x\n")
+        );
+        assert_eq!(
+            Err(ProgramError::InvalidInput("".to_string())),
+            run(vec![3, 0, 99], "")
+        );
+    }
+}
+
+#[cfg(test)]
+mod limits_tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(code: Code, limits: Limits) -> ProgramResult<()> {
+        Program::new(code).run(&mut Cursor::new(""), &mut Cursor::new(Vec::new()), &limits)
+    }
+
+    #[test]
+    fn test_no_limits() {
+        assert_eq!(Ok(()), run(vec![104, 1, 104, 2, 99], Limits::default()));
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let limits = Limits {
+            max_steps: Some(100),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Err(ProgramError::StepLimitExceeded(100)),
+            run(vec![1105, 1, 0], limits.clone())
+        );
+        assert_eq!(Ok(()), run(vec![104, 1, 99], limits));
+    }
+
+    #[test]
+    fn test_max_memory() {
+        let limits = Limits {
+            max_memory: Some(4),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Err(ProgramError::MemoryLimitExceeded(4)),
+            run(vec![104, 1, 99, 0, 0], limits.clone())
+        );
+        assert_eq!(Ok(()), run(vec![104, 1, 99], limits));
+    }
+
+    #[test]
+    fn test_max_memory_of_extended_memory() {
+        let limits = Limits {
+            max_memory: Some(1000),
+            ..Limits::default()
+        };
+        let run = |code, limits: &Limits| {
+            Program::with_dialect(code, Dialect::DAY09).run(
+                &mut Cursor::new(""),
+                &mut Cursor::new(Vec::new()),
+                limits,
+            )
+        };
+
+        assert_eq!(
+            Err(ProgramError::MemoryLimitExceeded(1000)),
+            run(vec![1101, 1, 1, 1 << 60, 99], &limits)
+        );
+        assert_eq!(
+            Err(ProgramError::AddressOutOfBounds(1 << 60)),
+            run(vec![1101, 1, 1, 1 << 60, 99], &Limits::default())
+        );
+        assert_eq!(Ok(()), run(vec![1101, 1, 1, 999, 99], &limits));
+    }
+
+    #[test]
+    fn test_max_outputs() {
+        let limits = Limits {
+            max_outputs: Some(3),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Err(ProgramError::OutputLimitExceeded(3)),
+            run(vec![104, 1, 1105, 1, 0], limits.clone())
+        );
+        assert_eq!(Ok(()), run(vec![104, 1, 104, 2, 99], limits));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(10)),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            Err(ProgramError::Timeout(Duration::from_millis(10))),
+            run(vec![1105, 1, 0], limits)
+        );
+    }
+
+    #[test]
+    fn test_set_option() {
+        let mut limits = Limits::default();
+
+        assert_eq!(Ok(true), limits.set_option("--max-steps", "100"));
+        assert_eq!(Ok(true), limits.set_option("--max-memory", "4096"));
+        assert_eq!(Ok(true), limits.set_option("--timeout", "0.5"));
+        assert_eq!(Ok(false), limits.set_option("--record", "x"));
+        assert_eq!(
+            Limits {
+                max_steps: Some(100),
+                max_memory: Some(4096),
+                max_outputs: None,
+                timeout: Some(Duration::from_millis(500)),
+            },
+            limits
+        );
+
+        assert_eq!(
+            Err("Invalid --max-outputs -1, expected a number".to_string()),
+            limits.set_option("--max-outputs", "-1")
+        );
+        assert_eq!(
+            Err("Invalid --timeout inf, expected a number".to_string()),
+            limits.set_option("--timeout", "inf")
+        );
+    }
+}
+
+#[cfg(test)]
+mod property_tests {
+
+    use super::*;
+    use crate::opcodes::*;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+
+    fn parameter_mode() -> impl Strategy<Value = ParameterMode> {
+        prop_oneof![
+            Just(ParameterMode::Position),
+            Just(ParameterMode::Immediate),
+            Just(ParameterMode::Relative)
+        ]
+    }
+
+    fn opcode() -> impl Strategy<Value = Opcode> {
+        prop::sample::select(OPERATIONS).prop_flat_map(|op| {
+            prop::collection::vec(parameter_mode(), op.arity).prop_map(move |modes| Opcode {
+                number: op.number,
+                modes,
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn opcode_decode_never_panics(i in any::<Word>()) {
+            let _ = Opcode::decode(i);
+        }
+
+        #[test]
+        fn opcode_encode_decode_round_trips(opcode in opcode()) {
+            prop_assert_eq!(Opcode::decode(opcode.encode()), Ok(opcode));
+        }
+
+        #[test]
+        fn opcode_decode_encode_round_trips(i in any::<Word>()) {
+            if let Ok(opcode) = Opcode::decode(i) {
+                prop_assert_eq!(Opcode::decode(opcode.encode()), Ok(opcode));
+            }
+        }
+
+        #[test]
+        fn instr_decode_encode_round_trips(code in prop::collection::vec(any::<Word>(), 0..5)) {
+            if let Ok(instr) = Instr::decode(&code) {
+                let code = instr.encode();
+                prop_assert_eq!(Ok(instr), Instr::decode(&code));
+            }
+        }
+
+        #[test]
+        fn pc_advances_by_instr_size(
+            opcode in opcode(),
+            params in prop::collection::vec(0..8 as Word, 3),
+            input in 0..100 as Word,
+        ) {
+            prop_assume!(![JT, JF, FIN].contains(&opcode.number));
+
+            let arity = opcode.modes.len();
+            let instr = Instr(opcode, params[..arity].to_vec());
+            let size = instr.size();
+            let mut code = instr.encode();
+            code.resize(8, 0);
+            let mut program = Program::with_dialect(code, Dialect::DAY09);
+
+            program
+                .step(&mut Cursor::new(input.to_string()), &mut Cursor::new(Vec::new()))
+                .unwrap();
+
+            prop_assert_eq!(size, program.pc);
+        }
+
+        #[test]
+        fn step_never_panics(
+            code in prop::collection::vec(any::<Word>(), 0..32),
+            input in ".*",
+        ) {
+            let mut program = Program::new(code);
+            let mut r#in = Cursor::new(input);
+
+            for _ in 0..64 {
+                if program.finished || program.step(&mut r#in, &mut Cursor::new(Vec::new())).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod parameter_mode_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(ParameterMode::Position), ParameterMode::parse(0));
+        assert_eq!(Ok(ParameterMode::Immediate), ParameterMode::parse(1));
+        assert_eq!(Ok(ParameterMode::Relative), ParameterMode::parse(2));
+        assert_eq!(
+            Err(ProgramError::UnknownParameterMode(3)),
+            ParameterMode::parse(3)
+        );
+    }
+}
+
+#[cfg(test)]
+mod conformance_tests {
+
+    use super::*;
+    use intcode_conformance::{Engine, Feature, Outcome};
+    use std::io::Cursor;
+
+    fn run(image: &[Word], input: &[Word], dialect: Dialect) -> Outcome {
+        let mut program = Program::with_dialect(image.to_vec(), dialect);
+        let mut r#in = Cursor::new(input.iter().map(|i| format!("{}\n", i)).collect::<String>());
+        let mut output = vec![];
+
+        while !program.finished {
+            output.extend(program.step_collecting(&mut r#in).unwrap());
+        }
+
+        Outcome {
+            output,
+            memory: program.code,
+        }
+    }
+
+    struct Day05;
+    impl Engine for Day05 {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            run(image, input, Dialect::DAY05)
+        }
+    }
+
+    struct Day05Wide;
+    impl Engine for Day05Wide {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+            Feature::LargeNumbers,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            let dialect = Dialect {
+                word_bits: 64,
+                ..Dialect::DAY05
+            };
+            run(image, input, dialect)
+        }
+    }
+
+    struct Day09;
+    impl Engine for Day09 {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+            Feature::RelativeBase,
+            Feature::ExtendedMemory,
+            Feature::LargeNumbers,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            run(image, input, Dialect::DAY09)
+        }
+    }
+
+    struct Day02;
+    impl Engine for Day02 {
+        const FEATURES: &'static [Feature] = &[];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            run(image, input, Dialect::DAY02)
+        }
+    }
+
+    #[test]
+    fn test_conformance() {
+        assert_eq!(23, intcode_conformance::check::<Day05>());
+    }
+
+    #[test]
+    fn test_conformance_with_64_bit_words() {
+        assert_eq!(25, intcode_conformance::check::<Day05Wide>());
+    }
+
+    #[test]
+    fn test_conformance_of_the_day09_dialect() {
+        assert_eq!(26, intcode_conformance::check::<Day09>());
+    }
+
+    #[test]
+    fn test_conformance_of_the_day02_dialect() {
+        assert_eq!(5, intcode_conformance::check::<Day02>());
+    }
+}
+
+#[cfg(test)]
+mod dialect_tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(code: Code, dialect: Dialect) -> ProgramResult<()> {
+        Program::with_dialect(code, dialect).run(
+            &mut Cursor::new("1\n"),
+            &mut Cursor::new(Vec::new()),
+            &Limits::default(),
+        )
+    }
+
+    #[test]
+    fn test_day02_dialect_rejects_day05_instructions() {
+        assert_eq!(
+            Err(ProgramError::UnsupportedOpcode(3)),
+            run(vec![1, 0, 0, 0, 3, 0, 99], Dialect::DAY02)
+        );
+        assert_eq!(
+            Err(ProgramError::UnsupportedParameterMode(
+                ParameterMode::Immediate
+            )),
+            run(vec![1101, 1, 1, 0, 99], Dialect::DAY02)
+        );
+        assert_eq!(Ok(()), run(vec![1101, 1, 1, 0, 3, 0, 99], Dialect::DAY05));
+    }
+
+    fn double(
+        program: &mut Program,
+        p: &[Word],
+        _: &mut dyn BufRead,
+        _: &mut dyn Write,
+    ) -> ProgramResult<Flow> {
+        program.write(p[1], p[0] * 2)?;
+        Ok(Flow::Next)
+    }
+
+    #[test]
+    fn test_day09_dialect_has_relative_mode_and_extended_memory() {
+        // ARB #10; ADD #7, #0, [rb+5]; OUT [15]; FIN
+        let code = vec![109, 10, 21101, 7, 0, 5, 4, 15, 99];
+
+        assert_eq!(
+            Err(ProgramError::UnsupportedOpcode(opcodes::ARB)),
+            run(code.clone(), Dialect::DAY05)
+        );
+
+        let mut program = Program::with_dialect(code, Dialect::DAY09);
+        let mut out = Cursor::new(Vec::new());
+        program
+            .run(&mut Cursor::new(""), &mut out, &Limits::default())
+            .unwrap();
+
+        assert_eq!(b"7".to_vec(), out.into_inner());
+        assert_eq!(10, program.relative_base);
+        assert_eq!(16, program.code.len());
+    }
+
+    #[test]
+    fn test_register_extension() {
+        // DBL #21, [0]; OUT [0]; FIN
+        let code = vec![142, 21, 0, 4, 0, 99];
+        let mut dialect = Dialect::DAY05;
+
+        assert_eq!(
+            Err(ProgramError::UnknownOpcode(142)),
+            run(code.clone(), dialect.clone())
+        );
+
+        dialect.register(Operation {
+            number: 42,
+            mnemonic: "DBL",
+            arity: 2,
+            writes: &[1],
+            handler: double,
+        });
+
+        let mut program = Program::with_dialect(code, dialect);
+        let mut out = Cursor::new(Vec::new());
+        program
+            .run(&mut Cursor::new(""), &mut out, &Limits::default())
+            .unwrap();
+
+        assert_eq!(42, program.code[0]);
+        assert_eq!("42", std::str::from_utf8(out.get_ref()).unwrap());
+    }
+
+    #[test]
+    fn test_word_size() {
+        let code = vec![1102, 65536, 65536, 0, 99];
+
+        assert_eq!(
+            Err(ProgramError::Overflow),
+            run(code.clone(), Dialect::DAY05)
+        );
+        assert_eq!(
+            Ok(()),
+            run(
+                code,
+                Dialect {
+                    word_bits: 64,
+                    ..Dialect::DAY05
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Loading of Intcode images.
+//!
+//! Images are either text, where the words are separated by commas and/or
+//! newlines and `#` starts a comment running to the end of the line, or
+//! packed, which is the magic bytes `ICPK` followed by the number of words
+//! and then the words themselves, all as zigzag encoded LEB128 varints.
+use super::{Code, Word};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+const MAGIC: &[u8] = b"ICPK";
+
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    Io(io::ErrorKind),
+    /// The text is not UTF-8 from the given byte offset
+    InvalidUtf8(usize),
+    /// A token that is not a word, at a one-based line and column
+    InvalidToken {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    /// A packed image is truncated or has a varint longer than 64 bits at
+    /// the given byte offset
+    InvalidPacked(usize),
+}
+
+pub type LoadResult<T> = Result<T, LoadError>;
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e.kind())
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(kind) => write!(f, "{:?}", kind),
+            LoadError::InvalidUtf8(offset) => write!(f, "invalid UTF-8 at byte {}", offset),
+            LoadError::InvalidToken {
+                line,
+                column,
+                token,
+            } => write!(f, "invalid word {:?} at {}:{}", token, line, column),
+            LoadError::InvalidPacked(offset) => {
+                write!(f, "invalid packed image at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl From<LoadError> for aoc::RunError {
+    fn from(e: LoadError) -> Self {
+        aoc::RunError::Input(format!("Could not load image: {}", e))
+    }
+}
+
+/// Where to load an image from. `-` stands for stdin.
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Stdin,
+    Path(PathBuf),
+}
+
+impl From<&str> for Source {
+    fn from(s: &str) -> Self {
+        match s {
+            "-" => Source::Stdin,
+            path => Source::Path(path.into()),
+        }
+    }
+}
+
+/// Loads an image in either format from `source`.
+pub fn load(source: &Source) -> LoadResult<Code> {
+    match source {
+        Source::Stdin => read(io::stdin().lock()),
+        Source::Path(path) => read(std::fs::File::open(path)?),
+    }
+}
+
+/// Reads an image in either format, telling them apart by the magic bytes.
+pub fn read<R>(mut read: R) -> LoadResult<Code>
+where
+    R: Read,
+{
+    let mut bytes = vec![];
+    read.read_to_end(&mut bytes)?;
+
+    if bytes.starts_with(MAGIC) {
+        parse_packed(&bytes)
+    } else {
+        parse_text(
+            std::str::from_utf8(&bytes).map_err(|e| LoadError::InvalidUtf8(e.valid_up_to()))?,
+        )
+    }
+}
+
+pub fn parse_text(s: &str) -> LoadResult<Code> {
+    let mut code = vec![];
+
+    for (line_idx, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let tokens = line.split(',').collect::<Vec<_>>();
+
+        let mut column = 1;
+        for (idx, token) in tokens.iter().enumerate() {
+            let trimmed = token.trim();
+
+            // Allow a trailing comma at the end of the line
+            if !(trimmed.is_empty() && idx == tokens.len() - 1) {
+                let offset = token.chars().count() - token.trim_start().chars().count();
+
+                code.push(trimmed.parse().map_err(|_| LoadError::InvalidToken {
+                    line: line_idx + 1,
+                    column: column + offset,
+                    token: trimmed.to_string(),
+                })?);
+            }
+
+            column += token.chars().count() + 1;
+        }
+    }
+
+    Ok(code)
+}
+
+pub fn parse_packed(bytes: &[u8]) -> LoadResult<Code> {
+    let mut offset = MAGIC.len();
+    if !bytes.starts_with(MAGIC) {
+        return Err(LoadError::InvalidPacked(0));
+    }
+
+    let len = read_varint(bytes, &mut offset)?;
+    let len = usize::try_from(len).map_err(|_| LoadError::InvalidPacked(MAGIC.len()))?;
+
+    // Don't trust the length to allocate, each word takes at least a byte
+    let mut code = Vec::with_capacity(len.min(bytes.len()));
+    for _ in 0..len {
+        let n = read_varint(bytes, &mut offset)?;
+        code.push((n >> 1) as Word ^ -((n & 1) as Word));
+    }
+
+    Ok(code)
+}
+
+pub fn write_packed<W>(code: &[Word], mut write: W) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut bytes = MAGIC.to_vec();
+
+    write_varint(code.len() as u64, &mut bytes);
+    for &word in code {
+        write_varint(((word << 1) ^ (word >> 63)) as u64, &mut bytes);
+    }
+
+    write.write_all(&bytes)
+}
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> LoadResult<u64> {
+    let start = *offset;
+    let mut n = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset).ok_or(LoadError::InvalidPacked(start))?;
+        *offset += 1;
+
+        // Only the lowest bit of the tenth byte is left to fill 64 bits
+        if shift == 63 && byte & 0x7f > 1 {
+            return Err(LoadError::InvalidPacked(start));
+        }
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+
+    Err(LoadError::InvalidPacked(start))
+}
+
+fn write_varint(mut n: u64, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+
+        if n == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(Ok(vec![1, 0, 0, 3, 99]), parse_text("1,0,0,3,99\n"));
+        assert_eq!(Ok(vec![1, -2, 3]), parse_text(" 1, -2 ,3 "));
+        assert_eq!(Ok(vec![]), parse_text(""));
+    }
+
+    #[test]
+    fn test_parse_text_with_comments_and_newlines() {
+        let s = "
+        # Adds the first two words
+        1101, 1, 2, 0,   # Add
+        99               # Fin
+        ";
+
+        assert_eq!(Ok(vec![1101, 1, 2, 0, 99]), parse_text(s));
+    }
+
+    #[test]
+    fn test_parse_text_reports_the_position_of_invalid_tokens() {
+        assert_eq!(
+            Err(LoadError::InvalidToken {
+                line: 1,
+                column: 3,
+                token: "2x".to_string()
+            }),
+            parse_text("1,2x,3")
+        );
+        assert_eq!(
+            Err(LoadError::InvalidToken {
+                line: 2,
+                column: 5,
+                token: "".to_string()
+            }),
+            parse_text("1,\n2,3,,4")
+        );
+        assert_eq!(
+            Err(LoadError::InvalidToken {
+                line: 1,
+                column: 4,
+                token: "99999999999999999999".to_string()
+            }),
+            parse_text("1, 99999999999999999999")
+        );
+        assert_eq!(
+            Err(LoadError::InvalidToken {
+                line: 1,
+                column: 4,
+                token: "2x".to_string()
+            }),
+            parse_text("1,\u{3000}2x")
+        );
+    }
+
+    #[test]
+    fn test_read_reports_invalid_utf8() {
+        assert_eq!(
+            Err(LoadError::InvalidUtf8(2)),
+            read(Cursor::new(b"1,\xff".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_packed_round_trip() {
+        let code = vec![1, 0, -1, 99, Word::MAX, Word::MIN, 1125];
+        let mut bytes = vec![];
+
+        write_packed(&code, &mut bytes).unwrap();
+
+        assert!(bytes.starts_with(MAGIC));
+        assert_eq!(Ok(code.clone()), parse_packed(&bytes));
+        assert_eq!(Ok(code), read(Cursor::new(bytes)));
+    }
+
+    #[test]
+    fn test_parse_packed_errors() {
+        assert_eq!(Err(LoadError::InvalidPacked(0)), parse_packed(b"1,2"));
+        // Two words announced but only one given
+        assert_eq!(
+            Err(LoadError::InvalidPacked(6)),
+            parse_packed(b"ICPK\x02\x02")
+        );
+        // A word larger than 64 bits
+        assert_eq!(
+            Err(LoadError::InvalidPacked(5)),
+            parse_packed(b"ICPK\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01")
+        );
+        // A tenth byte with more than the 64th bit
+        assert_eq!(
+            Err(LoadError::InvalidPacked(5)),
+            parse_packed(b"ICPK\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02")
+        );
+        assert_eq!(
+            Ok(vec![Word::MIN]),
+            parse_packed(b"ICPK\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01")
+        );
+    }
+
+    #[test]
+    fn test_source_from_str() {
+        assert_eq!(Source::Stdin, Source::from("-"));
+        assert_eq!(Source::Path("input.txt".into()), Source::from("input.txt"));
+    }
+}
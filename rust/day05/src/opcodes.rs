@@ -0,0 +1,305 @@
+//! The table of operations the machine can execute. Each entry describes how
+//! an opcode is decoded and executed, so adding an instruction is a matter
+//! of adding an entry, either here or through `Dialect::register`.
+use super::{Program, ProgramError, ProgramResult, Word};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+pub const ADD: Word = 1;
+pub const MUL: Word = 2;
+pub const IN: Word = 3;
+pub const OUT: Word = 4;
+pub const JT: Word = 5;
+pub const JF: Word = 6;
+pub const LT: Word = 7;
+pub const EQ: Word = 8;
+pub const ARB: Word = 9;
+pub const FIN: Word = 99;
+
+/// What the machine does after an operation has been executed
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+    /// Continue with the instruction following this one
+    Next,
+    Jump(Word),
+    Halt,
+}
+
+/// Executes an operation. The operands are the values of the parameters
+/// that are read, with their modes already applied, and the addresses of
+/// the parameters that are written.
+pub type Handler = fn(
+    program: &mut Program,
+    operands: &[Word],
+    r#in: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> ProgramResult<Flow>;
+
+#[derive(Clone, Copy)]
+pub struct Operation {
+    pub number: Word,
+    pub mnemonic: &'static str,
+    pub arity: usize,
+    /// The indexes of the parameters that are addresses written to
+    pub writes: &'static [usize],
+    pub handler: Handler,
+}
+impl Operation {
+    pub fn writes_to(&self, param: usize) -> bool {
+        self.writes.contains(&param)
+    }
+}
+impl PartialEq for Operation {
+    fn eq(&self, other: &Operation) -> bool {
+        (self.number, self.mnemonic, self.arity, self.writes)
+            == (other.number, other.mnemonic, other.arity, other.writes)
+    }
+}
+impl fmt::Debug for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Operation")
+            .field("number", &self.number)
+            .field("mnemonic", &self.mnemonic)
+            .field("arity", &self.arity)
+            .field("writes", &self.writes)
+            .finish()
+    }
+}
+
+pub const OPERATIONS: &[Operation] = &[
+    Operation {
+        number: ADD,
+        mnemonic: "ADD",
+        arity: 3,
+        writes: &[2],
+        handler: add,
+    },
+    Operation {
+        number: MUL,
+        mnemonic: "MUL",
+        arity: 3,
+        writes: &[2],
+        handler: mul,
+    },
+    Operation {
+        number: IN,
+        mnemonic: "IN",
+        arity: 1,
+        writes: &[0],
+        handler: input,
+    },
+    Operation {
+        number: OUT,
+        mnemonic: "OUT",
+        arity: 1,
+        writes: &[],
+        handler: output,
+    },
+    Operation {
+        number: JT,
+        mnemonic: "JT",
+        arity: 2,
+        writes: &[],
+        handler: jump_if_true,
+    },
+    Operation {
+        number: JF,
+        mnemonic: "JF",
+        arity: 2,
+        writes: &[],
+        handler: jump_if_false,
+    },
+    Operation {
+        number: LT,
+        mnemonic: "LT",
+        arity: 3,
+        writes: &[2],
+        handler: less_than,
+    },
+    Operation {
+        number: EQ,
+        mnemonic: "EQ",
+        arity: 3,
+        writes: &[2],
+        handler: equals,
+    },
+    Operation {
+        number: ARB,
+        mnemonic: "ARB",
+        arity: 1,
+        writes: &[],
+        handler: adjust_relative_base,
+    },
+    Operation {
+        number: FIN,
+        mnemonic: "FIN",
+        arity: 0,
+        writes: &[],
+        handler: finish,
+    },
+];
+
+/// Looks up `number` among `extensions` first and then the built in
+/// operations.
+pub fn lookup(number: Word, extensions: &[Operation]) -> Option<&Operation> {
+    extensions
+        .iter()
+        .chain(OPERATIONS)
+        .find(|op| op.number == number)
+}
+
+fn add(
+    program: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    program.write(p[2], p[0].checked_add(p[1]).ok_or(ProgramError::Overflow)?)?;
+    Ok(Flow::Next)
+}
+
+fn mul(
+    program: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    program.write(p[2], p[0].checked_mul(p[1]).ok_or(ProgramError::Overflow)?)?;
+    Ok(Flow::Next)
+}
+
+pub(crate) fn input(
+    program: &mut Program,
+    p: &[Word],
+    r#in: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    out.write_fmt(format_args!("Input: "))?;
+    out.flush()?;
+
+    let mut line = String::new();
+    r#in.read_line(&mut line)?;
+
+    let value = line
+        .trim()
+        .parse()
+        .map_err(|_| ProgramError::InvalidInput(line.trim().to_string()))?;
+    program.write(p[0], value)?;
+    Ok(Flow::Next)
+}
+
+pub(crate) fn output(
+    _: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    out.write_fmt(format_args!("{}", p[0]))?;
+    Ok(Flow::Next)
+}
+
+fn jump_if_true(
+    _: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    Ok(if p[0] != 0 {
+        Flow::Jump(p[1])
+    } else {
+        Flow::Next
+    })
+}
+
+fn jump_if_false(
+    _: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    Ok(if p[0] == 0 {
+        Flow::Jump(p[1])
+    } else {
+        Flow::Next
+    })
+}
+
+fn less_than(
+    program: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    program.write(p[2], if p[0] < p[1] { 1 } else { 0 })?;
+    Ok(Flow::Next)
+}
+
+fn equals(
+    program: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    program.write(p[2], if p[0] == p[1] { 1 } else { 0 })?;
+    Ok(Flow::Next)
+}
+
+fn adjust_relative_base(
+    program: &mut Program,
+    p: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    program.relative_base = program
+        .relative_base
+        .checked_add(p[0])
+        .ok_or(ProgramError::Overflow)?;
+    Ok(Flow::Next)
+}
+
+fn finish(
+    _: &mut Program,
+    _: &[Word],
+    _: &mut dyn BufRead,
+    _: &mut dyn Write,
+) -> ProgramResult<Flow> {
+    Ok(Flow::Halt)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_operations_are_unique() {
+        for (idx, op) in OPERATIONS.iter().enumerate() {
+            assert!(
+                OPERATIONS[idx + 1..]
+                    .iter()
+                    .all(|other| other.number != op.number),
+                "{} is defined twice",
+                op.number
+            );
+        }
+    }
+
+    #[test]
+    fn test_writes_are_within_arity() {
+        for op in OPERATIONS {
+            assert!(op.writes.iter().all(|&w| w < op.arity), "{:?}", op);
+        }
+    }
+
+    #[test]
+    fn test_lookup_prefers_extensions() {
+        let ext = Operation {
+            mnemonic: "NOP",
+            ..OPERATIONS[0]
+        };
+
+        assert_eq!(Some(&OPERATIONS[0]), lookup(ADD, &[]));
+        assert_eq!(Some(&ext), lookup(ADD, &[ext]));
+        assert_eq!(None, lookup(42, &[ext]));
+    }
+}
@@ -0,0 +1,546 @@
+//! A peephole optimizer and an interpreter for its output.
+//!
+//! The image is decoded up front into operations with their parameter modes
+//! resolved, rewriting on the way the instructions that can be simplified:
+//! additions of zero and multiplications by one become moves, operations on
+//! constants are folded into moves and jumps on a constant become
+//! unconditional jumps or are dropped.
+//!
+//! Instructions that are written to by position mode parameters of the
+//! instructions reachable from the start are not optimized. Writes that can't
+//! be known up front, through relative mode, throw away the operation of any
+//! instruction they land in. Wherever there is no operation, the instruction
+//! is decoded and executed by `Program::step`, so the program behaves just
+//! like with `Program::run`.
+use super::{
+    collect_output, opcodes, Instr, ParameterMode, Pc, Program, ProgramError, ProgramResult, Word,
+};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+/// The size of the largest built in instruction
+const MAX_SIZE: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operand {
+    Imm(Word),
+    Pos(Word),
+    Rel(Word),
+}
+
+/// An operation and the address of the instruction following it, where
+/// execution continues unless it jumps
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    Add(Operand, Operand, Operand, Pc),
+    Mul(Operand, Operand, Operand, Pc),
+    Move(Operand, Operand, Pc),
+    In(Operand, Pc),
+    Out(Operand, Pc),
+    Jump(Operand),
+    /// Jumps to the target if the condition is non-zero, or zero when the
+    /// flag is false
+    JumpIf(Operand, bool, Operand, Pc),
+    Lt(Operand, Operand, Operand, Pc),
+    Eq(Operand, Operand, Operand, Pc),
+    AdjustBase(Operand, Pc),
+    Nop(Pc),
+    Halt,
+}
+
+pub struct Optimized {
+    ops: Vec<Option<Op>>,
+    /// The size of the instruction at each address that has an operation
+    sizes: Vec<usize>,
+}
+
+impl Optimized {
+    /// Optimizes the code of `program` in its dialect. Every address is
+    /// decoded, since a jump could land anywhere.
+    pub fn new(program: &Program) -> Self {
+        let code = &program.code;
+        let dialect = &program.dialect;
+
+        let instrs = (0..code.len())
+            .map(|pc| Instr::decode_with(&code[pc..], &dialect.extensions).ok())
+            .collect::<Vec<_>>();
+
+        // The addresses written by the instructions reachable from the start
+        let mut written = BTreeSet::new();
+        let mut reached = BTreeSet::new();
+        let mut pending = vec![0];
+        while let Some(pc) = pending.pop() {
+            let instr = match instrs.get(pc) {
+                Some(Some(instr)) if reached.insert(pc) => instr,
+                _ => continue,
+            };
+            let Instr(opcode, params) = instr;
+
+            if let Some(operation) = dialect.operation(opcode.number) {
+                for &idx in operation.writes {
+                    if opcode.modes[idx] != ParameterMode::Relative {
+                        written.insert(params[idx]);
+                    }
+                }
+            }
+
+            let next = pc + instr.size();
+            match optimize(instr, next) {
+                Some(Op::Jump(Operand::Imm(target))) => pending.extend(Pc::try_from(target).ok()),
+                Some(Op::JumpIf(_, _, Operand::Imm(target), _)) => {
+                    pending.extend(Pc::try_from(target).ok());
+                    pending.push(next);
+                }
+                Some(Op::Halt) | Some(Op::Jump(_)) => {}
+                _ => pending.push(next),
+            }
+        }
+
+        let mut ops = vec![None; code.len()];
+        let mut sizes = vec![0; code.len()];
+        for (pc, instr) in instrs.iter().enumerate() {
+            let instr = match instr {
+                Some(instr) => instr,
+                None => continue,
+            };
+            let span = pc..pc + instr.size();
+
+            let builtin = !dialect
+                .extensions
+                .iter()
+                .any(|operation| operation.number == instr.0.number);
+            let written = span.clone().any(|addr| written.contains(&(addr as Word)));
+
+            if builtin && !written && dialect.check(&instr.0).is_ok() {
+                ops[pc] = optimize(instr, span.end);
+                sizes[pc] = instr.size();
+            }
+        }
+
+        Optimized { ops, sizes }
+    }
+
+    /// The operation of the instruction at `pc`, if it is optimized
+    pub fn op(&self, pc: Pc) -> Option<Op> {
+        self.ops.get(pc).copied().flatten()
+    }
+
+    /// Runs `program`, which must have the code it was optimized from, like
+    /// `Program::run` does without limits.
+    pub fn run(
+        &mut self,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+    ) -> ProgramResult<()> {
+        while !program.finished {
+            self.step(program, r#in, out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Executes the instruction at the `pc` of `program` like `Program::step`
+    /// does.
+    pub fn step(
+        &mut self,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+    ) -> ProgramResult<()> {
+        match self.op(program.pc) {
+            Some(op) => self.execute(op, program, r#in, out),
+            None => self.fallback(program, r#in, out),
+        }
+    }
+
+    /// Steps `program` like `step`, returning the value the instruction
+    /// output, if any.
+    pub fn step_collecting(
+        &mut self,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+    ) -> ProgramResult<Option<Word>> {
+        collect_output(|out| self.step(program, r#in, out))
+    }
+
+    /// Executes an instruction that isn't optimized through `Program`
+    fn fallback(
+        &mut self,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+    ) -> ProgramResult<()> {
+        let Instr(opcode, params) = program.decode()?;
+        let writes = program
+            .dialect
+            .operation(opcode.number)
+            .map_or(&[][..], |operation| operation.writes)
+            .iter()
+            .filter_map(|&idx| program.address(opcode.modes[idx], params[idx]).ok())
+            .collect::<Vec<_>>();
+
+        program.step(r#in, out)?;
+
+        for addr in writes {
+            self.invalidate(addr);
+        }
+        Ok(())
+    }
+
+    /// Throws away the operations of the instructions `addr` is part of
+    fn invalidate(&mut self, addr: Word) {
+        let addr = match usize::try_from(addr) {
+            Ok(addr) if addr < self.ops.len() => addr,
+            _ => return,
+        };
+
+        for pc in addr.saturating_sub(MAX_SIZE - 1)..=addr {
+            if pc + self.sizes[pc] > addr {
+                self.ops[pc] = None;
+            }
+        }
+    }
+
+    fn write(&mut self, program: &mut Program, place: Operand, value: Word) -> ProgramResult<()> {
+        let addr = address(program, place)?;
+
+        program.write(addr, value)?;
+        self.invalidate(addr);
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        op: Op,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+    ) -> ProgramResult<()> {
+        let get = |operand| value(program, operand);
+
+        let next = match op {
+            Op::Add(a, b, place, next) => {
+                let sum = get(a)?.checked_add(get(b)?).ok_or(ProgramError::Overflow)?;
+                self.write(program, place, sum)?;
+                next
+            }
+            Op::Mul(a, b, place, next) => {
+                let product = get(a)?.checked_mul(get(b)?).ok_or(ProgramError::Overflow)?;
+                self.write(program, place, product)?;
+                next
+            }
+            Op::Move(a, place, next) => {
+                let a = get(a)?;
+                self.write(program, place, a)?;
+                next
+            }
+            Op::Lt(a, b, place, next) => {
+                let lt = (get(a)? < get(b)?) as Word;
+                self.write(program, place, lt)?;
+                next
+            }
+            Op::Eq(a, b, place, next) => {
+                let eq = (get(a)? == get(b)?) as Word;
+                self.write(program, place, eq)?;
+                next
+            }
+            Op::In(place, next) => {
+                let addr = address(program, place)?;
+                opcodes::input(program, &[addr], r#in, out)?;
+                self.invalidate(addr);
+                next
+            }
+            Op::Out(a, next) => {
+                let a = get(a)?;
+                opcodes::output(program, &[a], r#in, out)?;
+                next
+            }
+            Op::Jump(target) => jump_target(get(target)?)?,
+            Op::JumpIf(cond, when, target, next) => {
+                // Like `Program`, the target is read even if it is not
+                // jumped to
+                let (cond, target) = (get(cond)?, get(target)?);
+                if (cond != 0) == when {
+                    jump_target(target)?
+                } else {
+                    next
+                }
+            }
+            Op::AdjustBase(a, next) => {
+                program.relative_base = program
+                    .relative_base
+                    .checked_add(get(a)?)
+                    .ok_or(ProgramError::Overflow)?;
+                next
+            }
+            Op::Nop(next) => next,
+            Op::Halt => {
+                program.finished = true;
+                program.pc
+            }
+        };
+
+        program.pc = next;
+        Ok(())
+    }
+}
+
+fn operand(mode: ParameterMode, param: Word) -> Operand {
+    match mode {
+        ParameterMode::Position => Operand::Pos(param),
+        ParameterMode::Immediate => Operand::Imm(param),
+        ParameterMode::Relative => Operand::Rel(param),
+    }
+}
+
+/// Decodes `instr`, followed by `next`, into an operation and rewrites it
+/// if it can be simplified
+fn optimize(instr: &Instr, next: Pc) -> Option<Op> {
+    use Operand::Imm;
+
+    let Instr(opcode, params) = instr;
+    let arg = |idx: usize| operand(opcode.modes[idx], params[idx]);
+    // The modes of written parameters other than relative act as positions
+    let place = |idx: usize| match opcode.modes[idx] {
+        ParameterMode::Relative => Operand::Rel(params[idx]),
+        _ => Operand::Pos(params[idx]),
+    };
+
+    Some(match opcode.number {
+        opcodes::ADD => match (arg(0), arg(1)) {
+            (Imm(a), Imm(b)) if a.checked_add(b).is_some() => Op::Move(Imm(a + b), place(2), next),
+            (Imm(0), a) | (a, Imm(0)) => Op::Move(a, place(2), next),
+            (a, b) => Op::Add(a, b, place(2), next),
+        },
+        opcodes::MUL => match (arg(0), arg(1)) {
+            (Imm(a), Imm(b)) if a.checked_mul(b).is_some() => Op::Move(Imm(a * b), place(2), next),
+            (Imm(1), a) | (a, Imm(1)) => Op::Move(a, place(2), next),
+            (a, b) => Op::Mul(a, b, place(2), next),
+        },
+        opcodes::LT => match (arg(0), arg(1)) {
+            (Imm(a), Imm(b)) => Op::Move(Imm((a < b) as Word), place(2), next),
+            (a, b) => Op::Lt(a, b, place(2), next),
+        },
+        opcodes::EQ => match (arg(0), arg(1)) {
+            (Imm(a), Imm(b)) => Op::Move(Imm((a == b) as Word), place(2), next),
+            (a, b) => Op::Eq(a, b, place(2), next),
+        },
+        opcodes::JT | opcodes::JF => {
+            let when = opcode.number == opcodes::JT;
+            match arg(0) {
+                Imm(cond) if (cond != 0) == when => Op::Jump(arg(1)),
+                Imm(_) if matches!(arg(1), Imm(_)) => Op::Nop(next),
+                cond => Op::JumpIf(cond, when, arg(1), next),
+            }
+        }
+        opcodes::IN => Op::In(place(0), next),
+        opcodes::OUT => Op::Out(arg(0), next),
+        opcodes::ARB => Op::AdjustBase(arg(0), next),
+        opcodes::FIN => Op::Halt,
+        _ => return None,
+    })
+}
+
+fn address(program: &Program, place: Operand) -> ProgramResult<Word> {
+    match place {
+        Operand::Rel(offset) => program.address(ParameterMode::Relative, offset),
+        Operand::Pos(addr) | Operand::Imm(addr) => Ok(addr),
+    }
+}
+
+fn value(program: &Program, operand: Operand) -> ProgramResult<Word> {
+    match operand {
+        Operand::Imm(value) => Ok(value),
+        Operand::Pos(addr) => program.read(addr),
+        Operand::Rel(offset) => program.read(program.address(ParameterMode::Relative, offset)?),
+    }
+}
+
+fn jump_target(target: Word) -> ProgramResult<Pc> {
+    usize::try_from(target).map_err(|_| ProgramError::AddressOutOfBounds(target))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{loader, Dialect};
+    use intcode_conformance::{Engine, Feature, Outcome};
+    use std::io::Cursor;
+
+    fn run(code: &[Word], input: &str, dialect: Dialect) -> (ProgramResult<()>, Program, String) {
+        let mut program = Program::with_dialect(code.to_vec(), dialect);
+        let mut optimized = Optimized::new(&program);
+        let mut out = Vec::new();
+
+        let result = optimized.run(&mut program, &mut Cursor::new(input), &mut out);
+        (result, program, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_rewrites() {
+        use Operand::*;
+
+        // ADD [20], #0 -> [20]; MUL #6, #7 -> [20]; EQ #1, #2 -> [20];
+        // JT #1, #18; JF #1, #0; FIN
+        let code = vec![
+            1001, 20, 0, 20, 1102, 6, 7, 20, 1108, 1, 2, 20, 1105, 1, 18, 1106, 1, 0, 99, 0, 0,
+        ];
+        let optimized = Optimized::new(&Program::new(code));
+
+        assert_eq!(Some(Op::Move(Pos(20), Pos(20), 4)), optimized.op(0));
+        assert_eq!(Some(Op::Move(Imm(42), Pos(20), 8)), optimized.op(4));
+        assert_eq!(Some(Op::Move(Imm(0), Pos(20), 12)), optimized.op(8));
+        assert_eq!(Some(Op::Jump(Imm(18))), optimized.op(12));
+        assert_eq!(Some(Op::Nop(18)), optimized.op(15));
+        assert_eq!(Some(Op::Halt), optimized.op(18));
+    }
+
+    #[test]
+    fn test_keeps_overflow() {
+        let code = vec![1102, Word::MAX, 2, 5, 99, 0];
+
+        assert_eq!(
+            Some(Op::Mul(
+                Operand::Imm(Word::MAX),
+                Operand::Imm(2),
+                Operand::Pos(5),
+                4
+            )),
+            Optimized::new(&Program::new(code.clone())).op(0)
+        );
+        assert_eq!(
+            Err(ProgramError::Overflow),
+            run(&code, "", Dialect::DAY09).0
+        );
+    }
+
+    #[test]
+    fn test_same_errors_as_program() {
+        // MUL [100], #0 -> [5] still reads the operand it multiplies by 0,
+        // and JT #0, [100] the target it does not jump to
+        let cases: &[&[Word]] = &[
+            &[1002, 100, 0, 5, 99, 0],
+            &[102, 0, 100, 5, 99, 0],
+            &[1002, 4, 0, 5, 99, 7],
+            &[105, 0, 100, 99],
+            &[106, 1, 100, 99],
+        ];
+
+        for code in cases {
+            let mut program = Program::new(code.to_vec());
+            let expected = program.run(
+                &mut Cursor::new(""),
+                &mut Vec::new(),
+                &crate::Limits::default(),
+            );
+
+            let (result, optimized, _) = run(code, "", Dialect::DAY05);
+            assert_eq!(expected, result, "{:?}", code);
+            assert_eq!(program.code, optimized.code, "{:?}", code);
+        }
+        assert_eq!(
+            Err(ProgramError::AddressOutOfBounds(100)),
+            run(cases[0], "", Dialect::DAY05).0
+        );
+    }
+
+    #[test]
+    fn test_refuses_written_instructions() {
+        // The first instruction patches the opcode of the second
+        let code = loader::parse_text(include_str!("../input.txt")).unwrap();
+        let optimized = Optimized::new(&Program::new(code));
+
+        assert!(optimized.op(2).is_some());
+        assert_eq!(None, optimized.op(6));
+        assert!(optimized.op(10).is_some());
+    }
+
+    #[test]
+    fn test_invalidates_relative_writes() {
+        // ARB #6; ADD #104, #0 -> [rb+0]; OUT [6]; FIN, where the `Add`
+        // patches the `Out` to output an immediate 6 instead
+        let code = [109, 6, 21101, 104, 0, 0, 4, 6, 99];
+        let (result, _, out) = run(&code, "", Dialect::DAY09);
+
+        assert_eq!(Ok(()), result);
+        assert_eq!("6", out);
+    }
+
+    #[test]
+    fn test_same_output_as_program() {
+        let code = loader::parse_text(include_str!("../input.txt")).unwrap();
+
+        for input in &["1\n", "5\n"] {
+            let mut program = Program::new(code.clone());
+            let mut expected = Vec::new();
+            program
+                .run(
+                    &mut Cursor::new(*input),
+                    &mut expected,
+                    &crate::Limits::default(),
+                )
+                .unwrap();
+
+            let (result, optimized, out) = run(&code, input, Dialect::DAY05);
+            assert_eq!(Ok(()), result);
+            assert_eq!(String::from_utf8(expected).unwrap(), out);
+            assert_eq!(program, optimized);
+        }
+    }
+
+    fn outcome(image: &[Word], input: &[Word], dialect: Dialect) -> Outcome {
+        let mut program = Program::with_dialect(image.to_vec(), dialect);
+        let mut optimized = Optimized::new(&program);
+        let mut r#in = Cursor::new(input.iter().map(|i| format!("{}\n", i)).collect::<String>());
+        let mut output = vec![];
+
+        while !program.finished {
+            output.extend(optimized.step_collecting(&mut program, &mut r#in).unwrap());
+        }
+
+        Outcome {
+            output,
+            memory: program.code,
+        }
+    }
+
+    struct Day05Optimized;
+    impl Engine for Day05Optimized {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            outcome(image, input, Dialect::DAY05)
+        }
+    }
+
+    struct Day09Optimized;
+    impl Engine for Day09Optimized {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+            Feature::RelativeBase,
+            Feature::ExtendedMemory,
+            Feature::LargeNumbers,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            outcome(image, input, Dialect::DAY09)
+        }
+    }
+
+    #[test]
+    fn test_conformance() {
+        assert_eq!(23, intcode_conformance::check::<Day05Optimized>());
+        assert_eq!(26, intcode_conformance::check::<Day09Optimized>());
+    }
+}
@@ -0,0 +1,469 @@
+//! Recording of the values a program inputs and outputs, so that a run can
+//! be replayed later without anyone at the terminal.
+//!
+//! A session file starts with `image` and a hash of the image the session
+//! was recorded with, followed by an `in` or `out` line with the value for
+//! every input and output, in order. Lines starting with `#` are comments.
+use super::{opcodes, Limits, Program, ProgramError, ProgramResult, Word};
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, Cursor, Read, Write};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Event {
+    In(Word),
+    Out(Word),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SessionError {
+    Io(io::ErrorKind),
+    /// A one-based line of a session file that could not be parsed
+    InvalidLine(usize, String),
+    /// The session was recorded with another image than the one replayed
+    ImageMismatch,
+    Program(ProgramError),
+    /// The replay did something else than what was recorded, first at the
+    /// event with the given index. `None` means there was no event.
+    Diverged {
+        index: usize,
+        expected: Option<Event>,
+        actual: Option<Event>,
+    },
+}
+
+pub type SessionResult<T> = Result<T, SessionError>;
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Io(kind) => write!(f, "{:?}", kind),
+            SessionError::InvalidLine(line, text) => {
+                write!(f, "invalid line {}: {:?}", line, text)
+            }
+            SessionError::ImageMismatch => write!(f, "recorded with another image"),
+            SessionError::Program(e) => e.fmt(f),
+            SessionError::Diverged { index, .. } => {
+                write!(f, "the replay diverged at event {}", index)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SessionError {
+    fn from(e: io::Error) -> Self {
+        SessionError::Io(e.kind())
+    }
+}
+
+impl From<ProgramError> for SessionError {
+    fn from(e: ProgramError) -> Self {
+        SessionError::Program(e)
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::In(value) => write!(f, "in {}", value),
+            Event::Out(value) => write!(f, "out {}", value),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Session {
+    /// The FNV-1a hash of the image
+    pub image: u64,
+    pub events: Vec<Event>,
+}
+
+impl Session {
+    /// Starts a session for `image`, the code before the program is run
+    pub fn new(image: &[Word]) -> Self {
+        Session {
+            image: hash(image),
+            events: vec![],
+        }
+    }
+
+    /// Runs `program` like `Program::run` does, adding every value input and
+    /// output to the session. Values are recorded even if the run fails, to
+    /// be able to reproduce the failure.
+    pub fn record(
+        &mut self,
+        program: &mut Program,
+        r#in: &mut dyn BufRead,
+        out: &mut dyn Write,
+        limits: &Limits,
+    ) -> ProgramResult<()> {
+        let (events, result) = run(program, r#in, out, limits);
+
+        self.events.extend(events);
+        result
+    }
+
+    /// Runs `program`, which must have the image the session was recorded
+    /// with, with the recorded input and checks that it inputs and outputs
+    /// the same values in the same order.
+    pub fn replay(
+        &self,
+        program: &mut Program,
+        out: &mut dyn Write,
+        limits: &Limits,
+    ) -> SessionResult<()> {
+        if hash(&program.code) != self.image {
+            return Err(SessionError::ImageMismatch);
+        }
+
+        let input = self
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                Event::In(value) => Some(format!("{}\n", value)),
+                Event::Out(_) => None,
+            })
+            .collect::<String>();
+
+        let (events, result) = run(program, &mut Cursor::new(input), out, limits);
+
+        if let Some(index) = (0..self.events.len().max(events.len()))
+            .find(|&idx| self.events.get(idx) != events.get(idx))
+        {
+            return Err(SessionError::Diverged {
+                index,
+                expected: self.events.get(index).copied(),
+                actual: events.get(index).copied(),
+            });
+        }
+        Ok(result?)
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "# Intcode session")?;
+        writeln!(w, "image {:016x}", self.image)?;
+        for event in &self.events {
+            writeln!(w, "{}", event)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(r: R) -> SessionResult<Session> {
+        let mut image = None;
+        let mut events = vec![];
+
+        for (idx, line) in r.lines().enumerate() {
+            let line = line?;
+            let invalid = || SessionError::InvalidLine(idx + 1, line.clone());
+
+            let mut tokens = line.split_whitespace();
+            let (kind, value) = match (tokens.next(), tokens.next(), tokens.next()) {
+                (None, _, _) => continue,
+                (Some(kind), _, _) if kind.starts_with('#') => continue,
+                (Some(kind), Some(value), None) => (kind, value),
+                _ => return Err(invalid()),
+            };
+
+            match kind {
+                "image" if image.is_none() => {
+                    image = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?)
+                }
+                "in" => events.push(Event::In(value.parse().map_err(|_| invalid())?)),
+                "out" => events.push(Event::Out(value.parse().map_err(|_| invalid())?)),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Session {
+            image: image
+                .ok_or_else(|| SessionError::InvalidLine(1, "missing image".to_string()))?,
+            events,
+        })
+    }
+}
+
+/// What the instruction being executed reads from the input and writes to
+/// the output. The values input and output are taken from there rather than
+/// from the memory, which may be mapped to devices that change when read.
+#[derive(Default)]
+struct Tap {
+    read: Vec<u8>,
+    written: Vec<u8>,
+}
+
+impl Tap {
+    /// The event of the instruction with `opcode` that just executed
+    fn take(&mut self, opcode: Word) -> Option<Event> {
+        let read = String::from_utf8(std::mem::take(&mut self.read)).ok()?;
+        let written = String::from_utf8(std::mem::take(&mut self.written)).ok()?;
+
+        match opcode {
+            opcodes::IN => read.trim().parse().ok().map(Event::In),
+            opcodes::OUT => written.parse().ok().map(Event::Out),
+            _ => None,
+        }
+    }
+}
+
+struct TapIn<'a> {
+    inner: &'a mut dyn BufRead,
+    tap: &'a RefCell<Tap>,
+}
+
+impl Read for TapIn<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.tap.borrow_mut().read.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl BufRead for TapIn<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The buffer is already filled, so this does not read any further
+        if let Ok(buf) = self.inner.fill_buf() {
+            let amt = amt.min(buf.len());
+            self.tap.borrow_mut().read.extend_from_slice(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+struct TapOut<'a> {
+    inner: &'a mut dyn Write,
+    tap: &'a RefCell<Tap>,
+}
+
+impl Write for TapOut<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.tap.borrow_mut().written.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Runs `program`, collecting the values it inputs and outputs
+fn run(
+    program: &mut Program,
+    r#in: &mut dyn BufRead,
+    out: &mut dyn Write,
+    limits: &Limits,
+) -> (Vec<Event>, ProgramResult<()>) {
+    let mut events = vec![];
+    let tap = RefCell::new(Tap::default());
+    // The events of an instruction are known once it has been executed, so
+    // its opcode is kept until the next instruction
+    let mut pending: Option<Word> = None;
+
+    let result = program.run_with(
+        &mut TapIn {
+            inner: r#in,
+            tap: &tap,
+        },
+        &mut TapOut {
+            inner: out,
+            tap: &tap,
+        },
+        limits,
+        |_, instr| {
+            if let Some(opcode) = pending.take() {
+                events.extend(tap.borrow_mut().take(opcode));
+            }
+            pending = Some(instr.0.number);
+        },
+    );
+
+    // The last instruction may have been an `In` or `Out`, which is recorded
+    // even if it failed after reading its value, such as on the write
+    if let Some(opcode) = pending {
+        events.extend(tap.borrow_mut().take(opcode));
+    }
+
+    (events, result)
+}
+
+/// FNV-1a of the words as little endian bytes
+fn hash(image: &[Word]) -> u64 {
+    image
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::devices::Keyboard;
+
+    /// Outputs 1 if the input is 8 and 0 otherwise, twice
+    const IS_EIGHT_TWICE: &[Word] = &[
+        3, 20, 1008, 20, 8, 21, 4, 21, 3, 20, 1008, 20, 8, 21, 4, 21, 99, 0, 0, 0, 0, 0,
+    ];
+
+    fn record(code: &[Word], input: &str) -> (Session, ProgramResult<()>) {
+        let mut session = Session::new(code);
+        let mut program = Program::new(code.to_vec());
+
+        let result = session.record(
+            &mut program,
+            &mut Cursor::new(input),
+            &mut io::sink(),
+            &Limits::default(),
+        );
+        (session, result)
+    }
+
+    fn replay(session: &Session, code: &[Word]) -> SessionResult<()> {
+        session.replay(
+            &mut Program::new(code.to_vec()),
+            &mut io::sink(),
+            &Limits::default(),
+        )
+    }
+
+    #[test]
+    fn test_record() {
+        let (session, result) = record(IS_EIGHT_TWICE, "8\n3\n");
+
+        assert_eq!(Ok(()), result);
+        assert_eq!(
+            vec![Event::In(8), Event::Out(1), Event::In(3), Event::Out(0)],
+            session.events
+        );
+    }
+
+    #[test]
+    fn test_record_keeps_events_before_a_failure() {
+        let (session, result) = record(IS_EIGHT_TWICE, "8\nx\n");
+
+        assert_eq!(Err(ProgramError::InvalidInput("x".to_string())), result);
+        assert_eq!(vec![Event::In(8), Event::Out(1)], session.events);
+    }
+
+    #[test]
+    fn test_record_devices() {
+        // OUT [100]; OUT [100]; FIN, with a keyboard at 100 that has one key
+        // to read
+        let code = [4, 100, 4, 100, 99];
+        let mut session = Session::new(&code);
+        let mut program = Program::new(code.to_vec());
+        let keyboard = program.devices.attach(100, Keyboard::new()).unwrap();
+        keyboard.lock().unwrap().type_str("a");
+        let mut out = vec![];
+
+        let result = session.record(
+            &mut program,
+            &mut Cursor::new(""),
+            &mut out,
+            &Limits::default(),
+        );
+
+        assert_eq!(Ok(()), result);
+        assert_eq!(b"97-1".to_vec(), out);
+        assert_eq!(vec![Event::Out(97), Event::Out(-1)], session.events);
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let (session, _) = record(IS_EIGHT_TWICE, "8\n-3\n");
+        let mut bytes = vec![];
+
+        session.write(&mut bytes).unwrap();
+
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.ends_with("in 8\nout 1\nin -3\nout 0\n"), "{}", text);
+        assert_eq!(Ok(session), Session::read(Cursor::new(bytes)));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(
+            Err(SessionError::InvalidLine(2, "in eight".to_string())),
+            Session::read(Cursor::new("image 1\nin eight\n"))
+        );
+        assert_eq!(
+            Err(SessionError::InvalidLine(2, "jump 3".to_string())),
+            Session::read(Cursor::new("image 1\njump 3\n"))
+        );
+        assert_eq!(
+            Err(SessionError::InvalidLine(1, "missing image".to_string())),
+            Session::read(Cursor::new("in 3\n"))
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let (session, _) = record(IS_EIGHT_TWICE, "8\n3\n");
+
+        assert_eq!(Ok(()), replay(&session, IS_EIGHT_TWICE));
+    }
+
+    #[test]
+    fn test_replay_reproduces_failures() {
+        let (session, _) = record(&[3, 0, 4, 0, 1, 0, 0, 10, 99], "5\n");
+
+        assert_eq!(
+            Err(SessionError::Program(ProgramError::AddressOutOfBounds(10))),
+            replay(&session, &[3, 0, 4, 0, 1, 0, 0, 10, 99])
+        );
+    }
+
+    #[test]
+    fn test_replay_reproduces_failed_writes_of_input() {
+        // IN [100]; FIN
+        let (session, result) = record(&[3, 100, 99], "5\n");
+
+        assert_eq!(Err(ProgramError::AddressOutOfBounds(100)), result);
+        assert_eq!(vec![Event::In(5)], session.events);
+        assert_eq!(
+            Err(SessionError::Program(ProgramError::AddressOutOfBounds(100))),
+            replay(&session, &[3, 100, 99])
+        );
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let (mut session, _) = record(IS_EIGHT_TWICE, "8\n3\n");
+        session.events[3] = Event::Out(1);
+
+        assert_eq!(
+            Err(SessionError::Diverged {
+                index: 3,
+                expected: Some(Event::Out(1)),
+                actual: Some(Event::Out(0)),
+            }),
+            replay(&session, IS_EIGHT_TWICE)
+        );
+
+        session.events.push(Event::Out(7));
+        session.events[3] = Event::Out(0);
+        assert_eq!(
+            Err(SessionError::Diverged {
+                index: 4,
+                expected: Some(Event::Out(7)),
+                actual: None,
+            }),
+            replay(&session, IS_EIGHT_TWICE)
+        );
+    }
+
+    #[test]
+    fn test_replay_checks_the_image() {
+        let (session, _) = record(IS_EIGHT_TWICE, "8\n3\n");
+        let mut code = IS_EIGHT_TWICE.to_vec();
+        code[4] = 9;
+
+        assert_eq!(Err(SessionError::ImageMismatch), replay(&session, &code));
+    }
+}
@@ -0,0 +1,106 @@
+//! The solution of the puzzle of day 5, where the program runs its
+//! diagnostic tests for the system ID given as input.
+use super::{loader, Limits, Program, ProgramResult, Word};
+use aoc::{Answer, Solution, SolveError, SolveResult};
+use std::io::Cursor;
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    type Input = Vec<Word>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Input> {
+        loader::parse_text(input).map_err(|e| SolveError::Input(e.to_string()))
+    }
+
+    /// The diagnostic code of the air conditioner unit
+    fn part1(&self, code: &Self::Input) -> SolveResult<Answer> {
+        solve(code, 1)
+    }
+
+    /// The diagnostic code of the thermal radiator controller
+    fn part2(&self, code: &Self::Input) -> SolveResult<Answer> {
+        solve(code, 5)
+    }
+}
+
+fn solve(code: &[Word], system: Word) -> SolveResult<Answer> {
+    let limits = Limits {
+        max_steps: Some(MAX_STEPS),
+        ..Limits::default()
+    };
+
+    match diagnostic_code(code.to_vec(), system, &limits) {
+        Ok(Some(value)) => Ok(value.into()),
+        Ok(None) => Err(SolveError::NoAnswer(
+            "the program output nothing".to_string(),
+        )),
+        Err(e) => Err(SolveError::NoAnswer(format!("the program failed: {}", e))),
+    }
+}
+
+/// The most instructions a diagnostic program may execute, since the input
+/// can't be trusted to halt
+const MAX_STEPS: u64 = 10_000_000;
+
+/// Runs the diagnostic program `code` for the system with ID `system` within
+/// `limits`, returning the last value it outputs
+pub fn diagnostic_code(
+    code: Vec<Word>,
+    system: Word,
+    limits: &Limits,
+) -> ProgramResult<Option<Word>> {
+    let mut r#in = Cursor::new(format!("{}\n", system));
+    let outputs = Program::new(code).run_collecting(&mut r#in, limits)?;
+
+    Ok(outputs.last().copied())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ProgramError;
+
+    #[test]
+    fn test_diagnostic_code() {
+        // Outputs 1 if the input is 8 and 0 otherwise
+        let code = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+
+        assert_eq!(
+            Ok(Some(1)),
+            diagnostic_code(code.clone(), 8, &Limits::default())
+        );
+        assert_eq!(Ok(Some(0)), diagnostic_code(code, 5, &Limits::default()));
+    }
+
+    #[test]
+    fn test_diagnostic_code_halts() {
+        let limits = Limits {
+            max_steps: Some(10),
+            ..Limits::default()
+        };
+
+        // JT #1, #0
+        assert_eq!(
+            Err(ProgramError::StepLimitExceeded(10)),
+            diagnostic_code(vec![1105, 1, 0], 1, &limits)
+        );
+    }
+
+    #[test]
+    fn test_solution() {
+        // Outputs 1 if the input is less than 5 and 0 otherwise
+        let code = Day05.parse("3,9,7,9,10,9,4,9,99,-1,5\n").unwrap();
+
+        assert_eq!(Ok(Answer::Number(1)), Day05.part1(&code));
+        assert_eq!(Ok(Answer::Number(0)), Day05.part2(&code));
+        assert_eq!(
+            Err(SolveError::NoAnswer(
+                "the program output nothing".to_string()
+            )),
+            Day05.part1(&vec![99])
+        );
+        assert!(Day05.parse("1,x").is_err());
+    }
+}
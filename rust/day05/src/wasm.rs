@@ -0,0 +1,949 @@
+//! Compilation of images to WebAssembly modules, to run them in a browser.
+//!
+//! Every instruction of the image is compiled with its parameter modes
+//! resolved, and the module dispatches on `pc` to them. An instruction checks
+//! first that its words are still the ones it was compiled from. If the
+//! program has written over it, or jumped somewhere that was not decoded as an
+//! instruction, an interpreter compiled into the module executes it instead.
+//!
+//! The module imports `env.input: () -> i64` and `env.output: (i64) -> ()`,
+//! and exports `run: () -> ()`, which runs the program until it halts, and
+//! its `memory` of 64 bit little endian words, with the globals `pc`, `rb`
+//! (the relative base) and `len` (the number of words in use). When the
+//! program fails, `run` traps after setting the globals `status` and `arg`,
+//! which `error` turns back into a `ProgramError`.
+//!
+//! Devices are not part of the module, and memory is limited to the 2^29
+//! words that fit in 32 bit WebAssembly memory.
+use super::opcodes::{self, OPERATIONS};
+use super::{Dialect, Instr, Opcode, ParameterMode, ProgramError, Word};
+use std::fmt;
+use wasm_encoder::{
+    BlockType, CodeSection, ConstExpr, DataSection, EntityType, ExportKind, ExportSection,
+    Function, FunctionSection, GlobalSection, GlobalType, ImportSection, InstructionSink, MemArg,
+    MemorySection, MemoryType, Module, TypeSection, ValType,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum CompileError {
+    /// The dialect has operations added to it, which only exist in Rust
+    Extensions,
+    /// The image does not fit in WebAssembly memory
+    TooLarge(usize),
+}
+
+pub type CompileResult<T> = Result<T, CompileError>;
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::Extensions => write!(f, "dialects with extensions can't be compiled"),
+            CompileError::TooLarge(len) => write!(f, "image of {} words is too large", len),
+        }
+    }
+}
+
+const PAGE_SIZE: u64 = 65536;
+const MAX_WORDS: u64 = (1 << 32) / 8;
+
+// The values of the `status` global
+const HALTED: i32 = 1;
+const UNKNOWN_OPCODE: i32 = 2;
+const UNKNOWN_PARAMETER_MODE: i32 = 3;
+const TRUNCATED_INSTR: i32 = 4;
+const PC_OUT_OF_BOUNDS: i32 = 5;
+const ADDRESS_OUT_OF_BOUNDS: i32 = 6;
+const OVERFLOW: i32 = 7;
+const UNSUPPORTED_OPCODE: i32 = 8;
+const UNSUPPORTED_PARAMETER_MODE: i32 = 9;
+
+// Functions, starting with the imports
+const INPUT: u32 = 0;
+const OUTPUT: u32 = 1;
+const FAIL: u32 = 2;
+const READ: u32 = 3;
+const WRITE: u32 = 4;
+const ADD: u32 = 5;
+const MUL: u32 = 6;
+const PARAM: u32 = 7;
+const PLACE: u32 = 8;
+const TARGET: u32 = 9;
+const STEP: u32 = 10;
+const RUN: u32 = 11;
+
+// Globals
+const PC: u32 = 0;
+const RB: u32 = 1;
+const LEN: u32 = 2;
+const STATUS: u32 = 3;
+const ARG: u32 = 4;
+
+// The locals of `step` and `run`: the pc of the instruction, its first word,
+// the modes of its parameters and its operands
+const L_PC: u32 = 0;
+const L_WORD: u32 = 1;
+const L_MODES: [u32; 3] = [2, 3, 4];
+const L_OPERANDS: [u32; 3] = [5, 6, 7];
+const LOCALS: u32 = 8;
+
+const WORD: MemArg = MemArg {
+    offset: 0,
+    align: 3,
+    memory_index: 0,
+};
+
+/// Compiles `code` into a module running it in `dialect`
+pub fn compile(code: &[Word], dialect: &Dialect) -> CompileResult<Vec<u8>> {
+    if !dialect.extensions.is_empty() {
+        return Err(CompileError::Extensions);
+    }
+    if code.len() as u64 > MAX_WORDS {
+        return Err(CompileError::TooLarge(code.len()));
+    }
+
+    let mut types = TypeSection::new();
+    let i64s = |n| vec![ValType::I64; n];
+    // input, output, fail, read, write, binary operations, step and run
+    types.ty().function(vec![], i64s(1));
+    types.ty().function(i64s(1), vec![]);
+    types
+        .ty()
+        .function(vec![ValType::I32, ValType::I64], vec![]);
+    types.ty().function(i64s(1), i64s(1));
+    types.ty().function(i64s(2), vec![]);
+    types.ty().function(i64s(2), i64s(1));
+    types.ty().function(vec![], vec![]);
+
+    let mut imports = ImportSection::new();
+    imports.import("env", "input", EntityType::Function(0));
+    imports.import("env", "output", EntityType::Function(1));
+
+    let mut functions = FunctionSection::new();
+    for ty in [2, 3, 4, 5, 5, 5, 5, 3, 6, 6] {
+        functions.function(ty);
+    }
+
+    let pages = (code.len() as u64 * 8).div_ceil(PAGE_SIZE).max(1);
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: pages,
+        maximum: Some(MAX_WORDS * 8 / PAGE_SIZE),
+        memory64: false,
+        shared: false,
+        page_size_log2: None,
+    });
+
+    let mut globals = GlobalSection::new();
+    for (val_type, init) in [
+        (ValType::I64, ConstExpr::i64_const(0)),
+        (ValType::I64, ConstExpr::i64_const(0)),
+        (ValType::I64, ConstExpr::i64_const(code.len() as i64)),
+        (ValType::I32, ConstExpr::i32_const(0)),
+        (ValType::I64, ConstExpr::i64_const(0)),
+    ] {
+        let global_type = GlobalType {
+            val_type,
+            mutable: true,
+            shared: false,
+        };
+        globals.global(global_type, &init);
+    }
+
+    let mut exports = ExportSection::new();
+    exports.export("run", ExportKind::Func, RUN);
+    exports.export("memory", ExportKind::Memory, 0);
+    for (name, global) in [
+        ("pc", PC),
+        ("rb", RB),
+        ("len", LEN),
+        ("status", STATUS),
+        ("arg", ARG),
+    ] {
+        exports.export(name, ExportKind::Global, global);
+    }
+
+    let mut codes = CodeSection::new();
+    for function in [
+        fail(),
+        read(dialect),
+        write(dialect),
+        add(),
+        mul(),
+        param(),
+        place(),
+        target(),
+        step(dialect),
+        run(code, dialect),
+    ] {
+        codes.function(&function);
+    }
+
+    let mut data = DataSection::new();
+    data.active(
+        0,
+        &ConstExpr::i32_const(0),
+        code.iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>(),
+    );
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&imports)
+        .section(&functions)
+        .section(&memories)
+        .section(&globals)
+        .section(&exports)
+        .section(&codes)
+        .section(&data);
+    Ok(module.finish())
+}
+
+/// The error a module failed with, from its `status` and `arg` globals
+pub fn error(status: i32, arg: Word) -> Option<ProgramError> {
+    Some(match status {
+        UNKNOWN_OPCODE => ProgramError::UnknownOpcode(arg),
+        UNKNOWN_PARAMETER_MODE => ProgramError::UnknownParameterMode(arg),
+        TRUNCATED_INSTR => ProgramError::TruncatedInstr,
+        PC_OUT_OF_BOUNDS => ProgramError::PcOutOfBounds(arg as usize),
+        ADDRESS_OUT_OF_BOUNDS => ProgramError::AddressOutOfBounds(arg),
+        OVERFLOW => ProgramError::Overflow,
+        UNSUPPORTED_OPCODE => ProgramError::UnsupportedOpcode(arg),
+        UNSUPPORTED_PARAMETER_MODE => {
+            ProgramError::UnsupportedParameterMode(ParameterMode::parse(arg).ok()?)
+        }
+        _ => return None,
+    })
+}
+
+/// The argument of a failure
+enum Arg {
+    Local(u32),
+    Const(i64),
+}
+
+fn fail_with(i: &mut InstructionSink, status: i32, arg: Arg) {
+    i.i32_const(status);
+    match arg {
+        Arg::Local(local) => i.local_get(local),
+        Arg::Const(value) => i.i64_const(value),
+    };
+    i.call(FAIL);
+}
+
+/// `fail(status: i32, arg: i64)`, which sets the globals and traps
+fn fail() -> Function {
+    let mut f = Function::new(vec![]);
+    f.instructions()
+        .local_get(0)
+        .global_set(STATUS)
+        .local_get(1)
+        .global_set(ARG)
+        .unreachable()
+        .end();
+    f
+}
+
+/// `read(addr: i64) -> i64`
+fn read(dialect: &Dialect) -> Function {
+    let mut f = Function::new(vec![]);
+    let mut i = f.instructions();
+
+    i.local_get(0).i64_const(0).i64_lt_s().if_(BlockType::Empty);
+    fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+    i.end();
+
+    i.local_get(0)
+        .global_get(LEN)
+        .i64_ge_s()
+        .if_(BlockType::Result(ValType::I64));
+    if dialect.extended_memory {
+        i.i64_const(0);
+    } else {
+        fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+        i.unreachable();
+    }
+    i.else_();
+    i.local_get(0)
+        .i64_const(8)
+        .i64_mul()
+        .i32_wrap_i64()
+        .i64_load(WORD);
+    i.end().end();
+    f
+}
+
+/// `write(addr: i64, value: i64)`, growing the memory if the dialect has
+/// extended memory
+fn write(dialect: &Dialect) -> Function {
+    let mut f = Function::new(vec![(1, ValType::I64)]);
+    let mut i = f.instructions();
+
+    if dialect.word_bits == 0 {
+        fail_with(&mut i, OVERFLOW, Arg::Const(0));
+    } else if dialect.word_bits < 64 {
+        let shift = i64::from(64 - dialect.word_bits);
+        i.local_get(1)
+            .i64_const(shift)
+            .i64_shl()
+            .i64_const(shift)
+            .i64_shr_s()
+            .local_get(1)
+            .i64_ne()
+            .if_(BlockType::Empty);
+        fail_with(&mut i, OVERFLOW, Arg::Const(0));
+        i.end();
+    }
+
+    i.local_get(0).i64_const(0).i64_lt_s().if_(BlockType::Empty);
+    fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+    i.end();
+
+    i.local_get(0)
+        .global_get(LEN)
+        .i64_ge_s()
+        .if_(BlockType::Empty);
+    if dialect.extended_memory {
+        i.local_get(0)
+            .i64_const(MAX_WORDS as i64)
+            .i64_ge_s()
+            .if_(BlockType::Empty);
+        fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+        i.end();
+
+        // Grow by the pages missing up to the one holding `addr`
+        i.local_get(0)
+            .i64_const(8)
+            .i64_mul()
+            .i64_const(PAGE_SIZE as i64)
+            .i64_div_u()
+            .i64_const(1)
+            .i64_add()
+            .memory_size(0)
+            .i64_extend_i32_u()
+            .i64_sub()
+            .local_tee(2)
+            .i64_const(0)
+            .i64_gt_s()
+            .if_(BlockType::Empty)
+            .local_get(2)
+            .i32_wrap_i64()
+            .memory_grow(0)
+            .i32_const(-1)
+            .i32_eq()
+            .if_(BlockType::Empty);
+        fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+        i.end().end();
+
+        i.local_get(0).i64_const(1).i64_add().global_set(LEN);
+    } else {
+        fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+    }
+    i.end();
+
+    i.local_get(0)
+        .i64_const(8)
+        .i64_mul()
+        .i32_wrap_i64()
+        .local_get(1)
+        .i64_store(WORD)
+        .end();
+    f
+}
+
+/// `add(a: i64, b: i64) -> i64`, failing on overflow
+fn add() -> Function {
+    let mut f = Function::new(vec![(1, ValType::I64)]);
+    let mut i = f.instructions();
+
+    i.local_get(0).local_get(1).i64_add().local_set(2);
+    // The sum overflowed if its sign differs from the sign of both terms
+    i.local_get(0)
+        .local_get(2)
+        .i64_xor()
+        .local_get(1)
+        .local_get(2)
+        .i64_xor()
+        .i64_and()
+        .i64_const(0)
+        .i64_lt_s()
+        .if_(BlockType::Empty);
+    fail_with(&mut i, OVERFLOW, Arg::Const(0));
+    i.end().local_get(2).end();
+    f
+}
+
+/// `mul(a: i64, b: i64) -> i64`, failing on overflow
+fn mul() -> Function {
+    let mut f = Function::new(vec![(1, ValType::I64)]);
+    let mut i = f.instructions();
+
+    i.local_get(0).local_get(1).i64_mul().local_set(2);
+    // -1 * MIN is the one overflow the division can't catch, since it
+    // overflows as well
+    i.local_get(0)
+        .i64_const(-1)
+        .i64_eq()
+        .if_(BlockType::Result(ValType::I32))
+        .local_get(1)
+        .i64_const(i64::MIN)
+        .i64_eq()
+        .else_()
+        .local_get(0)
+        .i64_const(0)
+        .i64_ne()
+        .if_(BlockType::Result(ValType::I32))
+        .local_get(2)
+        .local_get(0)
+        .i64_div_s()
+        .local_get(1)
+        .i64_ne()
+        .else_()
+        .i32_const(0)
+        .end()
+        .end()
+        .if_(BlockType::Empty);
+    fail_with(&mut i, OVERFLOW, Arg::Const(0));
+    i.end().local_get(2).end();
+    f
+}
+
+/// `param(mode: i64, param: i64) -> i64`, the value of a parameter that is
+/// read
+fn param() -> Function {
+    let mut f = Function::new(vec![]);
+    f.instructions()
+        .local_get(0)
+        .i64_const(ParameterMode::Immediate.encode())
+        .i64_eq()
+        .if_(BlockType::Result(ValType::I64))
+        .local_get(1)
+        .else_()
+        .local_get(0)
+        .local_get(1)
+        .call(PLACE)
+        .call(READ)
+        .end()
+        .end();
+    f
+}
+
+/// `place(mode: i64, param: i64) -> i64`, the address of a parameter that is
+/// written
+fn place() -> Function {
+    let mut f = Function::new(vec![]);
+    f.instructions()
+        .local_get(0)
+        .i64_const(ParameterMode::Relative.encode())
+        .i64_eq()
+        .if_(BlockType::Result(ValType::I64))
+        .global_get(RB)
+        .local_get(1)
+        .call(ADD)
+        .else_()
+        .local_get(1)
+        .end()
+        .end();
+    f
+}
+
+/// `target(target: i64) -> i64`, the target of a jump as long as it is an
+/// address
+fn target() -> Function {
+    let mut f = Function::new(vec![]);
+    let mut i = f.instructions();
+
+    i.local_get(0).i64_const(0).i64_lt_s().if_(BlockType::Empty);
+    fail_with(&mut i, ADDRESS_OUT_OF_BOUNDS, Arg::Local(0));
+    i.end().local_get(0).end();
+    f
+}
+
+/// Pushes the word at `L_PC` plus `offset`
+fn load_word(i: &mut InstructionSink, offset: i64) {
+    i.local_get(L_PC)
+        .i64_const(offset)
+        .i64_add()
+        .i64_const(8)
+        .i64_mul()
+        .i32_wrap_i64()
+        .i64_load(WORD);
+}
+
+/// Executes `operation` on the operands in their locals. Except for `Fin`,
+/// which sets the status to halted, `pc` is left at the next instruction.
+fn execute(i: &mut InstructionSink, number: Word, arity: usize) {
+    let [a, b, c] = L_OPERANDS;
+    let next = |i: &mut InstructionSink| {
+        i.local_get(L_PC)
+            .i64_const(1 + arity as i64)
+            .i64_add()
+            .global_set(PC);
+    };
+
+    match number {
+        opcodes::ADD | opcodes::MUL => {
+            let op = if number == opcodes::ADD { ADD } else { MUL };
+            i.local_get(c)
+                .local_get(a)
+                .local_get(b)
+                .call(op)
+                .call(WRITE);
+            next(i);
+        }
+        opcodes::LT | opcodes::EQ => {
+            i.local_get(c).local_get(a).local_get(b);
+            if number == opcodes::LT {
+                i.i64_lt_s();
+            } else {
+                i.i64_eq();
+            }
+            i.i64_extend_i32_u().call(WRITE);
+            next(i);
+        }
+        opcodes::IN => {
+            i.local_get(a).call(INPUT).call(WRITE);
+            next(i);
+        }
+        opcodes::OUT => {
+            i.local_get(a).call(OUTPUT);
+            next(i);
+        }
+        opcodes::JT | opcodes::JF => {
+            i.local_get(a).i64_const(0);
+            if number == opcodes::JT {
+                i.i64_ne();
+            } else {
+                i.i64_eq();
+            }
+            i.if_(BlockType::Empty)
+                .local_get(b)
+                .call(TARGET)
+                .global_set(PC)
+                .else_();
+            next(i);
+            i.end();
+        }
+        opcodes::ARB => {
+            i.global_get(RB).local_get(a).call(ADD).global_set(RB);
+            next(i);
+        }
+        _ => {
+            i.i32_const(HALTED).global_set(STATUS);
+        }
+    }
+}
+
+/// `step()`, which decodes and executes the instruction at `pc` like
+/// `Program::step` does
+fn step(dialect: &Dialect) -> Function {
+    let mut f = Function::new(vec![(LOCALS, ValType::I64)]);
+    let mut i = f.instructions();
+
+    i.global_get(PC).local_set(L_PC);
+    i.local_get(L_PC)
+        .global_get(LEN)
+        .i64_gt_s()
+        .if_(BlockType::Empty);
+    fail_with(&mut i, PC_OUT_OF_BOUNDS, Arg::Local(L_PC));
+    i.end();
+    i.local_get(L_PC)
+        .global_get(LEN)
+        .i64_eq()
+        .if_(BlockType::Empty);
+    fail_with(&mut i, TRUNCATED_INSTR, Arg::Const(0));
+    i.end();
+
+    load_word(&mut i, 0);
+    i.local_set(L_WORD);
+
+    for operation in OPERATIONS {
+        i.local_get(L_WORD)
+            .i64_const(100)
+            .i64_rem_s()
+            .i64_const(operation.number)
+            .i64_eq()
+            .if_(BlockType::Empty);
+
+        for (idx, &l_mode) in L_MODES.iter().enumerate().take(operation.arity) {
+            i.local_get(L_WORD)
+                .i64_const(100 * 10i64.pow(idx as u32))
+                .i64_div_s()
+                .i64_const(10)
+                .i64_rem_s()
+                .local_tee(l_mode)
+                .i64_const(ParameterMode::Relative.encode())
+                .i64_gt_s()
+                .if_(BlockType::Empty);
+            fail_with(&mut i, UNKNOWN_PARAMETER_MODE, Arg::Local(l_mode));
+            i.end();
+        }
+
+        i.local_get(L_PC)
+            .i64_const(operation.arity as i64)
+            .i64_add()
+            .global_get(LEN)
+            .i64_ge_s()
+            .if_(BlockType::Empty);
+        fail_with(&mut i, TRUNCATED_INSTR, Arg::Const(0));
+        i.end();
+
+        if !dialect.opcodes.contains(&operation.number) {
+            fail_with(&mut i, UNSUPPORTED_OPCODE, Arg::Const(operation.number));
+            i.return_().end();
+            continue;
+        }
+
+        for &l_mode in L_MODES.iter().take(operation.arity) {
+            for mode in [
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Relative,
+            ] {
+                if dialect.modes.contains(&mode) {
+                    continue;
+                }
+                i.local_get(l_mode)
+                    .i64_const(mode.encode())
+                    .i64_eq()
+                    .if_(BlockType::Empty);
+                fail_with(
+                    &mut i,
+                    UNSUPPORTED_PARAMETER_MODE,
+                    Arg::Const(mode.encode()),
+                );
+                i.end();
+            }
+        }
+
+        for idx in 0..operation.arity {
+            i.local_get(L_MODES[idx]);
+            load_word(&mut i, 1 + idx as i64);
+            i.call(if operation.writes_to(idx) {
+                PLACE
+            } else {
+                PARAM
+            })
+            .local_set(L_OPERANDS[idx]);
+        }
+
+        execute(&mut i, operation.number, operation.arity);
+        i.return_().end();
+    }
+
+    fail_with(&mut i, UNKNOWN_OPCODE, Arg::Local(L_WORD));
+    i.end();
+    f
+}
+
+/// `run()`, which dispatches on `pc` to the compiled instructions until the
+/// program halts, falling back to `step`
+fn run(code: &[Word], dialect: &Dialect) -> Function {
+    let mut f = Function::new(vec![(LOCALS, ValType::I64)]);
+    let mut i = f.instructions();
+    let n = code.len() as u32;
+
+    i.loop_(BlockType::Empty).block(BlockType::Empty);
+    for _ in 0..n {
+        i.block(BlockType::Empty);
+    }
+
+    // Inside the innermost block, the block ending before the instruction at
+    // `pc` is `pc` levels out, and the fallback is `n` levels out
+    i.global_get(PC).i64_const(n as i64).i64_ge_u().br_if(n);
+    i.global_get(PC).i32_wrap_i64().br_table(0..n, n);
+
+    for pc in 0..n {
+        i.end();
+        // The fallback is `n - 1 - pc` levels out and the loop one more
+        let fallback = n - 1 - pc;
+
+        let instr = match Instr::decode(&code[pc as usize..]) {
+            Ok(instr) if dialect.check(&instr.0).is_ok() => instr,
+            _ => {
+                i.br(fallback);
+                continue;
+            }
+        };
+        compile_instr(&mut i, pc as Word, &instr, fallback);
+
+        if instr.0.number == opcodes::FIN {
+            i.return_();
+        } else {
+            i.br(fallback + 1);
+        }
+    }
+
+    i.end();
+    i.call(STEP)
+        .global_get(STATUS)
+        .if_(BlockType::Empty)
+        .return_()
+        .end()
+        .br(0)
+        .end()
+        .end();
+    f
+}
+
+/// Executes `instr`, compiled from the words at `pc`, unless they have been
+/// written over, in which case it branches out to the fallback
+fn compile_instr(i: &mut InstructionSink, pc: Word, instr: &Instr, fallback: u32) {
+    let Instr(Opcode { number, modes }, params) = instr;
+
+    i.i64_const(pc).local_set(L_PC);
+    for (offset, word) in instr.encode().into_iter().enumerate() {
+        load_word(i, offset as i64);
+        i.i64_const(word).i64_ne().br_if(fallback);
+    }
+
+    let operation = opcodes::lookup(*number, &[]).unwrap();
+    for (idx, (&mode, &param)) in modes.iter().zip(params).enumerate() {
+        if mode == ParameterMode::Relative {
+            i.global_get(RB).i64_const(param).call(ADD);
+        } else {
+            i.i64_const(param);
+        }
+        if mode != ParameterMode::Immediate && !operation.writes_to(idx) {
+            i.call(READ);
+        }
+        i.local_set(L_OPERANDS[idx]);
+    }
+
+    execute(i, *number, params.len());
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{loader, Program, ProgramResult};
+    use intcode_conformance::{Engine, Feature, Outcome};
+    use std::convert::TryInto;
+    use std::io::Cursor;
+    use wasmi::{Caller, Linker, Store};
+
+    struct Host {
+        input: Vec<Word>,
+        output: Vec<Word>,
+    }
+
+    /// Runs `code` compiled to WebAssembly, returning the result, the output
+    /// and the memory in use
+    fn run_wasm(
+        code: &[Word],
+        input: &[Word],
+        dialect: &Dialect,
+    ) -> (ProgramResult<()>, Vec<Word>, Vec<Word>) {
+        let engine = wasmi::Engine::default();
+        let module = wasmi::Module::new(&engine, &compile(code, dialect).unwrap()[..]).unwrap();
+        let mut store = Store::new(
+            &engine,
+            Host {
+                input: input.iter().rev().copied().collect(),
+                output: vec![],
+            },
+        );
+
+        let mut linker = Linker::new(&engine);
+        linker
+            .func_wrap("env", "input", |mut caller: Caller<Host>| {
+                caller
+                    .data_mut()
+                    .input
+                    .pop()
+                    .ok_or_else(|| wasmi::Error::new("no input"))
+            })
+            .unwrap()
+            .func_wrap("env", "output", |mut caller: Caller<Host>, value: i64| {
+                caller.data_mut().output.push(value)
+            })
+            .unwrap();
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+
+        let result = instance
+            .get_typed_func::<(), ()>(&store, "run")
+            .unwrap()
+            .call(&mut store, ())
+            .map_err(|_| {
+                let global = |name| instance.get_global(&store, name).unwrap().get(&store);
+                error(
+                    global("status").i32().unwrap(),
+                    global("arg").i64().unwrap(),
+                )
+                .unwrap_or(ProgramError::InputClosed)
+            });
+
+        let len = instance.get_global(&store, "len").unwrap().get(&store);
+        let memory = instance
+            .get_memory(&store, "memory")
+            .unwrap()
+            .data(&store)
+            .chunks(8)
+            .take(len.i64().unwrap() as usize)
+            .map(|bytes| Word::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+
+        (result, store.into_data().output, memory)
+    }
+
+    /// Runs `code` with `Program`, the same way as `run_wasm`
+    fn run_program(
+        code: &[Word],
+        input: &[Word],
+        dialect: &Dialect,
+    ) -> (ProgramResult<()>, Vec<Word>, Vec<Word>) {
+        let mut program = Program::with_dialect(code.to_vec(), dialect.clone());
+        let mut r#in = Cursor::new(input.iter().map(|i| format!("{}\n", i)).collect::<String>());
+        let mut output = vec![];
+
+        let result = loop {
+            if program.finished {
+                break Ok(());
+            }
+            match program.step_collecting(&mut r#in) {
+                Ok(value) => output.extend(value),
+                Err(e) => break Err(e),
+            }
+        };
+
+        (result, output, program.code)
+    }
+
+    fn assert_same(code: &[Word], input: &[Word], dialect: &Dialect) {
+        assert_eq!(
+            run_program(code, input, dialect),
+            run_wasm(code, input, dialect),
+            "{:?}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_compile() {
+        let (result, output, memory) = run_wasm(&[1, 0, 0, 0, 99], &[], &Dialect::DAY02);
+
+        assert_eq!(Ok(()), result);
+        assert!(output.is_empty());
+        assert_eq!(vec![2, 0, 0, 0, 99], memory);
+    }
+
+    #[test]
+    fn test_refuses_extensions() {
+        let mut dialect = Dialect::DAY05;
+        dialect.register(OPERATIONS[0]);
+
+        assert_eq!(Err(CompileError::Extensions), compile(&[99], &dialect));
+    }
+
+    #[test]
+    fn test_same_as_program() {
+        let code = loader::parse_text(include_str!("../input.txt")).unwrap();
+
+        for input in [1, 5] {
+            assert_same(&code, &[input], &Dialect::DAY05);
+        }
+    }
+
+    #[test]
+    fn test_written_instructions() {
+        // ADD #104, #0 -> [4]; OUT [6]; FIN, where the `Add` patches the
+        // `Out` to output an immediate 6 instead
+        assert_same(&[1101, 104, 0, 4, 4, 6, 99], &[], &Dialect::DAY05);
+        // ARB #6; ADD #104, #0 -> [rb+0]; OUT [6]; FIN, the same through a
+        // relative parameter
+        assert_same(&[109, 6, 21101, 104, 0, 0, 4, 6, 99], &[], &Dialect::DAY09);
+    }
+
+    #[test]
+    fn test_same_errors_as_program() {
+        let cases: &[(&[Word], Dialect)] = &[
+            (&[1, 0, 0, 10, 99], Dialect::DAY05),
+            (&[1, 0, 0, 10, 99], Dialect::DAY09),
+            (&[1101, Word::MAX, 1, 0, 99], Dialect::DAY09),
+            (&[1101, 1 << 30, 1 << 30, 0, 99], Dialect::DAY05),
+            (
+                &[1101, 0, 0, 0, 99],
+                Dialect {
+                    word_bits: 0,
+                    ..Dialect::DAY05
+                },
+            ),
+            (&[1102, Word::MIN, -1, 0, 99], Dialect::DAY09),
+            (&[1102, -1, Word::MIN, 0, 99], Dialect::DAY09),
+            (&[3, 0, 99], Dialect::DAY02),
+            (&[109, 1, 99], Dialect::DAY05),
+            (&[1101, 1, 1], Dialect::DAY05),
+            (&[1105, 1, -1], Dialect::DAY05),
+            (&[1105, 1, 3], Dialect::DAY05),
+            (&[1105, 1, 7, 99], Dialect::DAY05),
+            (&[301, 0, 0, 0, 99], Dialect::DAY05),
+            (&[42, 0], Dialect::DAY05),
+            (&[-1], Dialect::DAY05),
+            // Jumps into the parameters of the `Add`
+            (&[1101, 1005, 0, 7, 1105, 1, 1, 99], Dialect::DAY05),
+        ];
+
+        for (code, dialect) in cases {
+            assert_same(code, &[], dialect);
+        }
+    }
+
+    #[test]
+    fn test_extended_memory() {
+        // ADD #1, #2 -> [100000]; OUT [100000]; OUT [200000]; FIN
+        let code = [1101, 1, 2, 100000, 4, 100000, 4, 200000, 99];
+
+        assert_same(&code, &[], &Dialect::DAY09);
+        assert_eq!(100001, run_wasm(&code, &[], &Dialect::DAY09).2.len());
+    }
+
+    struct Day05Wasm;
+    impl Engine for Day05Wasm {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            outcome(image, input, Dialect::DAY05)
+        }
+    }
+
+    struct Day09Wasm;
+    impl Engine for Day09Wasm {
+        const FEATURES: &'static [Feature] = &[
+            Feature::Io,
+            Feature::ParameterModes,
+            Feature::Jumps,
+            Feature::Comparisons,
+            Feature::RelativeBase,
+            Feature::ExtendedMemory,
+            Feature::LargeNumbers,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            outcome(image, input, Dialect::DAY09)
+        }
+    }
+
+    fn outcome(image: &[Word], input: &[Word], dialect: Dialect) -> Outcome {
+        let (result, output, memory) = run_wasm(image, input, &dialect);
+        result.unwrap();
+
+        Outcome { output, memory }
+    }
+
+    #[test]
+    fn test_conformance() {
+        assert_eq!(23, intcode_conformance::check::<Day05Wasm>());
+        assert_eq!(26, intcode_conformance::check::<Day09Wasm>());
+    }
+}
@@ -0,0 +1,477 @@
+//! Conformance suite for the Intcode machines of Advent of Code 2019.
+//!
+//! Every example program published in the puzzle texts is listed in [`CASES`]
+//! together with its input and expected output. A machine implements
+//! [`Engine`] and calls [`check`] from its tests to run every case it has the
+//! features for.
+//! https://adventofcode.com/2019/day/2
+//! https://adventofcode.com/2019/day/5
+//! https://adventofcode.com/2019/day/9
+
+use std::fmt;
+
+pub type Word = i64;
+
+/// A part of the Intcode specification that a case depends on.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Feature {
+    /// `In` (3) and `Out` (4)
+    Io,
+    /// Immediate parameter mode
+    ParameterModes,
+    /// `JT` (5) and `JF` (6)
+    Jumps,
+    /// `LT` (7) and `EQ` (8)
+    Comparisons,
+    /// Relative parameter mode and the relative base offset instruction (9)
+    RelativeBase,
+    /// Reads and writes beyond the end of the image
+    ExtendedMemory,
+    /// Values that do not fit in 32 bits
+    LargeNumbers,
+}
+
+#[derive(Debug)]
+pub struct Case {
+    pub name: &'static str,
+    pub features: &'static [Feature],
+    pub image: &'static [Word],
+    pub input: &'static [Word],
+    pub output: &'static [Word],
+    /// The start of the memory once the program has halted, if the puzzle
+    /// text gives it.
+    pub memory: Option<&'static [Word]>,
+}
+
+/// What a program left behind once it halted.
+#[derive(Debug, PartialEq, Default)]
+pub struct Outcome {
+    pub output: Vec<Word>,
+    pub memory: Vec<Word>,
+}
+
+/// An Intcode machine that can be put through the suite.
+pub trait Engine {
+    /// The features this engine implements. Cases depending on anything else
+    /// are skipped.
+    const FEATURES: &'static [Feature];
+
+    /// Runs `image` until it halts, feeding it `input`.
+    fn run(image: &[Word], input: &[Word]) -> Outcome;
+}
+
+impl Case {
+    pub fn is_supported_by(&self, features: &[Feature]) -> bool {
+        self.features.iter().all(|f| features.contains(f))
+    }
+
+    /// Returns a description of what went wrong, if anything.
+    pub fn verify(&self, outcome: &Outcome) -> Option<String> {
+        if outcome.output != self.output {
+            return Some(format!(
+                "expected output {:?}, got {:?}",
+                self.output, outcome.output
+            ));
+        }
+
+        match self.memory {
+            Some(memory) if !outcome.memory.starts_with(memory) => Some(format!(
+                "expected memory {:?}, got {:?}",
+                memory, outcome.memory
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (input {:?})", self.name, self.input)
+    }
+}
+
+/// Runs every supported case on `E` and panics with a list of the failing
+/// ones. Returns the number of cases that were run.
+pub fn check<E: Engine>() -> usize {
+    let cases = CASES
+        .iter()
+        .filter(|case| case.is_supported_by(E::FEATURES))
+        .collect::<Vec<_>>();
+
+    let failures = cases
+        .iter()
+        .filter_map(|case| {
+            case.verify(&E::run(case.image, case.input))
+                .map(|reason| format!("{}: {}", case, reason))
+        })
+        .collect::<Vec<_>>();
+
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} conformance cases failed:\n{}",
+            failures.len(),
+            cases.len(),
+            failures.join("\n")
+        );
+    }
+
+    cases.len()
+}
+
+use Feature::*;
+
+const EQUAL_TO_8_POSITION: &[Word] = &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+const LESS_THAN_8_POSITION: &[Word] = &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+const EQUAL_TO_8_IMMEDIATE: &[Word] = &[3, 3, 1108, -1, 8, 3, 4, 3, 99];
+const LESS_THAN_8_IMMEDIATE: &[Word] = &[3, 3, 1107, -1, 8, 3, 4, 3, 99];
+const JUMP_POSITION: &[Word] = &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+const JUMP_IMMEDIATE: &[Word] = &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+const COMPARE_TO_8: &[Word] = &[
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+    1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105,
+    1, 46, 98, 99,
+];
+const QUINE: &[Word] = &[
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+pub const CASES: &[Case] = &[
+    // Day 2
+    Case {
+        name: "day02 example",
+        features: &[],
+        image: &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+        input: &[],
+        output: &[],
+        memory: Some(&[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]),
+    },
+    Case {
+        name: "day02 add",
+        features: &[],
+        image: &[1, 0, 0, 0, 99],
+        input: &[],
+        output: &[],
+        memory: Some(&[2, 0, 0, 0, 99]),
+    },
+    Case {
+        name: "day02 mul",
+        features: &[],
+        image: &[2, 3, 0, 3, 99],
+        input: &[],
+        output: &[],
+        memory: Some(&[2, 3, 0, 6, 99]),
+    },
+    Case {
+        name: "day02 mul past halt",
+        features: &[],
+        image: &[2, 4, 4, 5, 99, 0],
+        input: &[],
+        output: &[],
+        memory: Some(&[2, 4, 4, 5, 99, 9801]),
+    },
+    Case {
+        name: "day02 self-modifying",
+        features: &[],
+        image: &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+        input: &[],
+        output: &[],
+        memory: Some(&[30, 1, 1, 4, 2, 5, 6, 0, 99]),
+    },
+    // Day 5
+    Case {
+        name: "day05 echo",
+        features: &[Io],
+        image: &[3, 0, 4, 0, 99],
+        input: &[42],
+        output: &[42],
+        memory: Some(&[42, 0, 4, 0, 99]),
+    },
+    Case {
+        name: "day05 immediate mul",
+        features: &[ParameterModes],
+        image: &[1002, 4, 3, 4, 33],
+        input: &[],
+        output: &[],
+        memory: Some(&[1002, 4, 3, 4, 99]),
+    },
+    Case {
+        name: "day05 negative add",
+        features: &[ParameterModes],
+        image: &[1101, 100, -1, 4, 0],
+        input: &[],
+        output: &[],
+        memory: Some(&[1101, 100, -1, 4, 99]),
+    },
+    Case {
+        name: "day05 equal to 8, position mode",
+        features: &[Io, Comparisons],
+        image: EQUAL_TO_8_POSITION,
+        input: &[8],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 equal to 8, position mode",
+        features: &[Io, Comparisons],
+        image: EQUAL_TO_8_POSITION,
+        input: &[7],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 less than 8, position mode",
+        features: &[Io, Comparisons],
+        image: LESS_THAN_8_POSITION,
+        input: &[7],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 less than 8, position mode",
+        features: &[Io, Comparisons],
+        image: LESS_THAN_8_POSITION,
+        input: &[8],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 equal to 8, immediate mode",
+        features: &[Io, ParameterModes, Comparisons],
+        image: EQUAL_TO_8_IMMEDIATE,
+        input: &[8],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 equal to 8, immediate mode",
+        features: &[Io, ParameterModes, Comparisons],
+        image: EQUAL_TO_8_IMMEDIATE,
+        input: &[9],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 less than 8, immediate mode",
+        features: &[Io, ParameterModes, Comparisons],
+        image: LESS_THAN_8_IMMEDIATE,
+        input: &[-3],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 less than 8, immediate mode",
+        features: &[Io, ParameterModes, Comparisons],
+        image: LESS_THAN_8_IMMEDIATE,
+        input: &[8],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 jump, position mode",
+        features: &[Io, Jumps],
+        image: JUMP_POSITION,
+        input: &[0],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 jump, position mode",
+        features: &[Io, Jumps],
+        image: JUMP_POSITION,
+        input: &[5],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 jump, immediate mode",
+        features: &[Io, ParameterModes, Jumps],
+        image: JUMP_IMMEDIATE,
+        input: &[0],
+        output: &[0],
+        memory: None,
+    },
+    Case {
+        name: "day05 jump, immediate mode",
+        features: &[Io, ParameterModes, Jumps],
+        image: JUMP_IMMEDIATE,
+        input: &[-1],
+        output: &[1],
+        memory: None,
+    },
+    Case {
+        name: "day05 compare to 8",
+        features: &[Io, ParameterModes, Jumps, Comparisons],
+        image: COMPARE_TO_8,
+        input: &[7],
+        output: &[999],
+        memory: None,
+    },
+    Case {
+        name: "day05 compare to 8",
+        features: &[Io, ParameterModes, Jumps, Comparisons],
+        image: COMPARE_TO_8,
+        input: &[8],
+        output: &[1000],
+        memory: None,
+    },
+    Case {
+        name: "day05 compare to 8",
+        features: &[Io, ParameterModes, Jumps, Comparisons],
+        image: COMPARE_TO_8,
+        input: &[9],
+        output: &[1001],
+        memory: None,
+    },
+    // Day 9
+    Case {
+        name: "day09 quine",
+        features: &[
+            Io,
+            ParameterModes,
+            Jumps,
+            Comparisons,
+            RelativeBase,
+            ExtendedMemory,
+        ],
+        image: QUINE,
+        input: &[],
+        output: QUINE,
+        memory: None,
+    },
+    Case {
+        name: "day09 16 digit product",
+        features: &[Io, ParameterModes, LargeNumbers],
+        image: &[1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+        input: &[],
+        output: &[1219070632396864],
+        memory: None,
+    },
+    Case {
+        name: "day09 large immediate",
+        features: &[Io, ParameterModes, LargeNumbers],
+        image: &[104, 1125899906842624, 99],
+        input: &[],
+        output: &[1125899906842624],
+        memory: None,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A reference machine covering the complete specification, so that the
+    /// expectations in the suite are checked against something.
+    struct Reference;
+    impl Engine for Reference {
+        const FEATURES: &'static [Feature] = &[
+            Io,
+            ParameterModes,
+            Jumps,
+            Comparisons,
+            RelativeBase,
+            ExtendedMemory,
+            LargeNumbers,
+        ];
+
+        fn run(image: &[Word], input: &[Word]) -> Outcome {
+            let mut memory = image.to_vec();
+            let mut input = input.iter();
+            let mut output = vec![];
+            let (mut pc, mut base) = (0, 0);
+
+            let addr = |memory: &mut Vec<Word>, pc: usize, base: Word, n: u32| {
+                let a = match memory[pc] / (10 * 10i64.pow(n)) % 10 {
+                    0 => memory[pc + n as usize] as usize,
+                    1 => pc + n as usize,
+                    2 => (base + memory[pc + n as usize]) as usize,
+                    m => panic!("Unknown parameter mode {}", m),
+                };
+                if a >= memory.len() {
+                    memory.resize(a + 1, 0);
+                }
+                a
+            };
+
+            loop {
+                macro_rules! p {
+                    ($n:expr) => {
+                        addr(&mut memory, pc, base, $n)
+                    };
+                }
+
+                pc = match memory[pc] % 100 {
+                    1 => {
+                        let (a, b, c) = (p!(1), p!(2), p!(3));
+                        memory[c] = memory[a] + memory[b];
+                        pc + 4
+                    }
+                    2 => {
+                        let (a, b, c) = (p!(1), p!(2), p!(3));
+                        memory[c] = memory[a] * memory[b];
+                        pc + 4
+                    }
+                    3 => {
+                        let a = p!(1);
+                        memory[a] = *input.next().unwrap();
+                        pc + 2
+                    }
+                    4 => {
+                        let a = p!(1);
+                        output.push(memory[a]);
+                        pc + 2
+                    }
+                    5 => {
+                        let (a, b) = (p!(1), p!(2));
+                        if memory[a] != 0 {
+                            memory[b] as usize
+                        } else {
+                            pc + 3
+                        }
+                    }
+                    6 => {
+                        let (a, b) = (p!(1), p!(2));
+                        if memory[a] == 0 {
+                            memory[b] as usize
+                        } else {
+                            pc + 3
+                        }
+                    }
+                    7 => {
+                        let (a, b, c) = (p!(1), p!(2), p!(3));
+                        memory[c] = (memory[a] < memory[b]) as Word;
+                        pc + 4
+                    }
+                    8 => {
+                        let (a, b, c) = (p!(1), p!(2), p!(3));
+                        memory[c] = (memory[a] == memory[b]) as Word;
+                        pc + 4
+                    }
+                    9 => {
+                        let a = p!(1);
+                        base += memory[a];
+                        pc + 2
+                    }
+                    99 => break,
+                    op => panic!("Unknown opcode {}", op),
+                };
+            }
+
+            Outcome { output, memory }
+        }
+    }
+
+    #[test]
+    fn test_reference_engine_passes_every_case() {
+        assert_eq!(CASES.len(), check::<Reference>());
+    }
+
+    #[test]
+    fn test_is_supported_by() {
+        let case = &CASES[5];
+
+        assert!(case.is_supported_by(&[Io]));
+        assert!(!case.is_supported_by(&[ParameterModes]));
+    }
+}
@@ -0,0 +1,501 @@
+//! Differential testing of Intcode engines. The same image and input is run
+//! on two engines in lockstep, one instruction at a time, until they halt or
+//! first disagree on the pc, the memory or the output.
+use day05::optimize::Optimized;
+use day05::{Dialect, Word};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Cursor;
+
+/// An Intcode engine that can be executed one instruction at a time
+pub trait Machine {
+    fn name(&self) -> &str;
+    /// Executes the next instruction, or describes why it could not
+    fn step(&mut self) -> Result<(), String>;
+    fn state(&self) -> State;
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct State {
+    pub pc: usize,
+    pub finished: bool,
+    pub memory: Vec<Word>,
+    /// Every value output so far
+    pub output: Vec<Word>,
+}
+
+/// What the engines disagreed on
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Difference {
+    Pc,
+    Finished,
+    Output,
+    Memory,
+    /// Only one of the engines failed, or they failed with different errors
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Divergence {
+    /// The number of instructions both engines executed before diverging
+    pub steps: u64,
+    pub difference: Difference,
+    pub names: [String; 2],
+    pub states: [State; 2],
+    /// Why each engine failed, if it did
+    pub errors: [Option<String>; 2],
+}
+
+/// The number of differing memory addresses that a divergence lists
+const MAX_LISTED: usize = 10;
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Difference::Pc => "pc",
+            Difference::Finished => "halting",
+            Difference::Output => "output",
+            Difference::Memory => "memory",
+            Difference::Error => "errors",
+        })
+    }
+}
+
+/// Prints the state of both engines, with the memory as the addresses where
+/// they differ
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} and {} diverged in {} after {} steps",
+            self.names[0], self.names[1], self.difference, self.steps
+        )?;
+
+        for ((name, state), error) in self.names.iter().zip(&self.states).zip(&self.errors) {
+            let status = match error {
+                Some(error) => format!("failed: {}", error),
+                None if state.finished => "halted".to_string(),
+                None => "running".to_string(),
+            };
+            writeln!(
+                f,
+                "  {}: pc {}, {}, output {:?}",
+                name, state.pc, status, state.output
+            )?;
+        }
+
+        let [a, b] = [&self.states[0].memory, &self.states[1].memory];
+        let differing = (0..a.len().max(b.len()))
+            .filter(|&addr| a.get(addr) != b.get(addr))
+            .collect::<Vec<_>>();
+        if differing.is_empty() {
+            return Ok(());
+        }
+
+        let word = |memory: &[Word], addr: usize| {
+            memory
+                .get(addr)
+                .map_or_else(|| "-".to_string(), ToString::to_string)
+        };
+        writeln!(f, "  memory differs at {} addresses:", differing.len())?;
+        for &addr in differing.iter().take(MAX_LISTED) {
+            writeln!(
+                f,
+                "  {:>8}: {:>12} {:>12}",
+                addr,
+                word(a, addr),
+                word(b, addr)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn compare(a: &State, b: &State) -> Option<Difference> {
+    if a.pc != b.pc {
+        Some(Difference::Pc)
+    } else if a.finished != b.finished {
+        Some(Difference::Finished)
+    } else if a.output != b.output {
+        Some(Difference::Output)
+    } else if a.memory != b.memory {
+        Some(Difference::Memory)
+    } else {
+        None
+    }
+}
+
+/// Steps `a` and `b` together until both have halted, both have failed or
+/// `max_steps` instructions have been executed, returning the number of
+/// instructions executed. Engines failing at the same step agree if they
+/// fail with the same error.
+pub fn lockstep(
+    a: &mut dyn Machine,
+    b: &mut dyn Machine,
+    max_steps: u64,
+) -> Result<u64, Box<Divergence>> {
+    let mut steps = 0;
+
+    loop {
+        let states = [a.state(), b.state()];
+        let errors = [None, None];
+        if let Some(difference) = compare(&states[0], &states[1]) {
+            return Err(divergence(a, b, steps, difference, states, errors));
+        }
+        if states[0].finished || steps == max_steps {
+            return Ok(steps);
+        }
+
+        match [a.step().err(), b.step().err()] {
+            [None, None] => steps += 1,
+            [Some(e), Some(f)] if e == f => return Ok(steps),
+            errors => {
+                let states = [a.state(), b.state()];
+                return Err(divergence(a, b, steps, Difference::Error, states, errors));
+            }
+        }
+    }
+}
+
+fn divergence(
+    a: &dyn Machine,
+    b: &dyn Machine,
+    steps: u64,
+    difference: Difference,
+    states: [State; 2],
+    errors: [Option<String>; 2],
+) -> Box<Divergence> {
+    Box::new(Divergence {
+        steps,
+        difference,
+        names: [a.name().to_string(), b.name().to_string()],
+        states,
+        errors,
+    })
+}
+
+/// The interpreter of day 2, which has no input or output and can't tell
+/// why an instruction failed
+pub struct Day02 {
+    program: day02::Program,
+}
+
+impl Day02 {
+    /// Fails if a word of `image` does not fit in the unsigned 32 bit words
+    /// of day 2
+    pub fn new(image: &[Word]) -> Result<Self, String> {
+        let code = image
+            .iter()
+            .enumerate()
+            .map(|(addr, &word)| {
+                u32::try_from(word)
+                    .map_err(|_| format!("word {} at {} does not fit in day02", word, addr))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Day02 {
+            program: day02::Program::new(code),
+        })
+    }
+}
+
+impl Machine for Day02 {
+    fn name(&self) -> &str {
+        "day02"
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        self.program
+            .try_tick()
+            .ok_or_else(|| "could not execute instruction".to_string())
+    }
+
+    fn state(&self) -> State {
+        State {
+            pc: self.program.pc,
+            finished: self.program.finished,
+            memory: self.program.code.iter().map(|&word| word.into()).collect(),
+            output: vec![],
+        }
+    }
+}
+
+/// The input of an engine of day 5, which reads a value per line
+fn input(input: &[Word]) -> Cursor<String> {
+    Cursor::new(input.iter().map(|i| format!("{}\n", i)).collect())
+}
+
+/// The interpreter of day 5, in any of its dialects
+pub struct Day05 {
+    name: String,
+    program: day05::Program,
+    input: Cursor<String>,
+    output: Vec<Word>,
+}
+
+impl Day05 {
+    pub fn new(name: &str, image: &[Word], input: &[Word], dialect: Dialect) -> Self {
+        Day05 {
+            name: name.to_string(),
+            program: day05::Program::with_dialect(image.to_vec(), dialect),
+            input: self::input(input),
+            output: vec![],
+        }
+    }
+}
+
+impl Machine for Day05 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        let value = self
+            .program
+            .step_collecting(&mut self.input)
+            .map_err(|e| format!("{:?}", e))?;
+
+        self.output.extend(value);
+        Ok(())
+    }
+
+    fn state(&self) -> State {
+        State {
+            pc: self.program.pc,
+            finished: self.program.finished,
+            memory: self.program.code.clone(),
+            output: self.output.clone(),
+        }
+    }
+}
+
+/// The peephole optimized interpreter of day 5
+pub struct Day05Optimized {
+    name: String,
+    program: day05::Program,
+    optimized: Optimized,
+    input: Cursor<String>,
+    output: Vec<Word>,
+}
+
+impl Day05Optimized {
+    pub fn new(name: &str, image: &[Word], input: &[Word], dialect: Dialect) -> Self {
+        let program = day05::Program::with_dialect(image.to_vec(), dialect);
+
+        Day05Optimized {
+            name: name.to_string(),
+            optimized: Optimized::new(&program),
+            program,
+            input: self::input(input),
+            output: vec![],
+        }
+    }
+}
+
+impl Machine for Day05Optimized {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        let value = self
+            .optimized
+            .step_collecting(&mut self.program, &mut self.input)
+            .map_err(|e| format!("{:?}", e))?;
+
+        self.output.extend(value);
+        Ok(())
+    }
+
+    fn state(&self) -> State {
+        State {
+            pc: self.program.pc,
+            finished: self.program.finished,
+            memory: self.program.code.clone(),
+            output: self.output.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use aoc::Solution;
+    use day05::loader;
+
+    const MAX_STEPS: u64 = 100_000;
+
+    fn day02_image(noun: Word, verb: Word) -> Vec<Word> {
+        let code = day02::Day02
+            .parse(include_str!("../../day02/input.txt"))
+            .unwrap();
+        let mut image = code.into_iter().map(Word::from).collect::<Vec<_>>();
+        image[1] = noun;
+        image[2] = verb;
+        image
+    }
+
+    /// Outputs one more than the engine it wraps
+    struct OffByOne(Day05);
+    impl Machine for OffByOne {
+        fn name(&self) -> &str {
+            "off by one"
+        }
+
+        fn step(&mut self) -> Result<(), String> {
+            self.0.step()
+        }
+
+        fn state(&self) -> State {
+            let mut state = self.0.state();
+            state.output.iter_mut().for_each(|value| *value += 1);
+            state
+        }
+    }
+
+    #[test]
+    fn test_day02_and_day05_agree() {
+        let image = day02_image(12, 2);
+        let mut day02 = Day02::new(&image).unwrap();
+        let mut day05 = Day05::new("day05", &image, &[], Dialect::DAY02);
+
+        let steps = lockstep(&mut day02, &mut day05, MAX_STEPS).unwrap();
+
+        assert!(steps > 0);
+        assert!(day05.program.finished);
+    }
+
+    #[test]
+    fn test_day05_and_optimized_agree() {
+        let image = loader::parse_text(include_str!("../../day05/input.txt")).unwrap();
+
+        for input in [1, 5] {
+            let mut day05 = Day05::new("day05", &image, &[input], Dialect::DAY05);
+            let mut optimized = Day05Optimized::new("optimized", &image, &[input], Dialect::DAY05);
+
+            assert!(lockstep(&mut day05, &mut optimized, MAX_STEPS).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_stops_at_max_steps() {
+        // JT #1, #0 loops forever
+        let image = [1105, 1, 0];
+        let mut a = Day05::new("a", &image, &[], Dialect::DAY05);
+        let mut b = Day05Optimized::new("b", &image, &[], Dialect::DAY05);
+
+        assert_eq!(
+            Ok(10),
+            lockstep(&mut a, &mut b, 10).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn test_only_one_failing() {
+        // MUL [5], [5] -> [0]; FIN, where the product fits in 32 bits only
+        // when unsigned
+        let image = [2, 5, 5, 0, 99, 50000];
+        let mut day02 = Day02::new(&image).unwrap();
+        let mut day05 = Day05::new("day05", &image, &[], Dialect::DAY02);
+
+        let divergence = lockstep(&mut day02, &mut day05, MAX_STEPS).unwrap_err();
+
+        assert_eq!(0, divergence.steps);
+        assert_eq!(Difference::Error, divergence.difference);
+        assert_eq!([None, Some("Overflow".to_string())], divergence.errors);
+        assert_eq!(2500000000, divergence.states[0].memory[0]);
+    }
+
+    #[test]
+    fn test_failing_alike() {
+        // ADD [-1], [0] -> [0]
+        let image = [1, -1, 0, 0, 99];
+        let mut day05 = Day05::new("day05", &image, &[], Dialect::DAY05);
+        let mut optimized = Day05Optimized::new("optimized", &image, &[], Dialect::DAY05);
+
+        assert_eq!(
+            Ok(0),
+            lockstep(&mut day05, &mut optimized, MAX_STEPS).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn test_failing_differently() {
+        let image = [98];
+        let mut day02 = Day02::new(&image).unwrap();
+        let mut day05 = Day05::new("day05", &image, &[], Dialect::DAY02);
+
+        let divergence = lockstep(&mut day02, &mut day05, MAX_STEPS).unwrap_err();
+
+        assert_eq!(Difference::Error, divergence.difference);
+        assert_eq!(
+            [
+                Some("could not execute instruction".to_string()),
+                Some("UnknownOpcode(98)".to_string())
+            ],
+            divergence.errors
+        );
+    }
+
+    #[test]
+    fn test_day02_rejects_words_out_of_range() {
+        assert_eq!(
+            Some("word -1 at 1 does not fit in day02".to_string()),
+            Day02::new(&[1, -1, 0, 0, 99]).err()
+        );
+    }
+
+    #[test]
+    fn test_output_divergence() {
+        // IN [0]; OUT [0]; FIN
+        let image = [3, 0, 4, 0, 99];
+        let mut day05 = Day05::new("day05", &image, &[42], Dialect::DAY05);
+        let mut off = OffByOne(Day05::new("", &image, &[42], Dialect::DAY05));
+
+        let divergence = lockstep(&mut day05, &mut off, MAX_STEPS).unwrap_err();
+
+        assert_eq!(
+            "day05 and off by one diverged in output after 2 steps
+  day05: pc 4, running, output [42]
+  off by one: pc 4, running, output [43]
+",
+            divergence.to_string()
+        );
+    }
+
+    #[test]
+    fn test_lists_memory_differences() {
+        let divergence = Divergence {
+            steps: 3,
+            difference: Difference::Memory,
+            names: ["a".to_string(), "b".to_string()],
+            states: [
+                State {
+                    pc: 4,
+                    finished: true,
+                    memory: vec![1, 2, 3],
+                    output: vec![],
+                },
+                State {
+                    pc: 4,
+                    finished: false,
+                    memory: vec![1, 5],
+                    output: vec![],
+                },
+            ],
+            errors: [None, Some("Overflow".to_string())],
+        };
+
+        assert_eq!(
+            "a and b diverged in memory after 3 steps
+  a: pc 4, halted, output []
+  b: pc 4, failed: Overflow, output []
+  memory differs at 2 addresses:
+         1:            2            5
+         2:            3            -
+",
+            divergence.to_string()
+        );
+    }
+}
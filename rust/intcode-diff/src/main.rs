@@ -0,0 +1,53 @@
+use aoc::{argument, exit_on_error, RunError, RunResult};
+use day05::loader::{self, Source};
+use day05::{Dialect, Word};
+use intcode_diff::{lockstep, Day02, Day05, Day05Optimized, Machine};
+
+const MAX_STEPS: u64 = 10_000_000;
+
+fn engine(name: &str, image: &[Word], input: &[Word]) -> RunResult<Box<dyn Machine>> {
+    Ok(match name {
+        "day02" => Box::new(Day02::new(image).map_err(RunError::Input)?),
+        "day05" => Box::new(Day05::new(name, image, input, Dialect::DAY05)),
+        "day09" => Box::new(Day05::new(name, image, input, Dialect::DAY09)),
+        "day05-optimized" => Box::new(Day05Optimized::new(name, image, input, Dialect::DAY05)),
+        "day09-optimized" => Box::new(Day05Optimized::new(name, image, input, Dialect::DAY09)),
+        _ => {
+            return Err(RunError::Usage(format!(
+            "Unknown engine {}, expected day02, day05, day09, day05-optimized or day09-optimized",
+            name
+        )))
+        }
+    })
+}
+
+/// Runs the image given as the first argument on the two engines given as
+/// the second and third arguments, with the rest of the arguments as input,
+/// and fails with where they diverge, if they do, as a solver error.
+fn main() {
+    exit_on_error(run(&std::env::args().skip(1).collect::<Vec<_>>()));
+}
+
+fn run(args: &[String]) -> RunResult<()> {
+    let name = argument(args, 0, "image")?;
+    let (name_a, name_b) = (argument(args, 1, "engine")?, argument(args, 2, "engine")?);
+
+    let image = loader::load(&Source::from(name))?;
+    let input = args[3..]
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| RunError::Input(format!("Invalid input {}, expected a number", value)))
+        })
+        .collect::<RunResult<Vec<Word>>>()?;
+
+    let mut a = engine(name_a, &image, &input)?;
+    let mut b = engine(name_b, &image, &input)?;
+
+    let steps = lockstep(a.as_mut(), b.as_mut(), MAX_STEPS)
+        .map_err(|divergence| RunError::Solver(divergence.to_string().trim_end().to_string()))?;
+
+    println!("{} and {} agree for {} steps", name_a, name_b, steps);
+    Ok(())
+}